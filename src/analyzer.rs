@@ -0,0 +1,384 @@
+use std::fmt;
+
+use crate::{
+    ast::{Expression, ParserError, Statement},
+    parser::Parser,
+    token::TokenKind,
+};
+
+/// Issues found by inspecting a program before evaluating it.
+/// Warnings don't stop the evaluation, they only point out code that is likely to fail.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Warning {
+    /// A division or modulo whose denominator is the constant `0`.
+    ZeroDenominator(Expression),
+    /// A function bound to a name which calls itself outside of a tail position.
+    NonTailRecursion(String),
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::ZeroDenominator(expr) => {
+                write!(f, "`{expr}` has a zero denominator and will always fail")
+            }
+            Warning::NonTailRecursion(name) => write!(
+                f,
+                "`{name}` calls itself outside of a tail position, so deep recursions grow the stack"
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Analyzer<'a> {
+    parser: Parser<'a>,
+    warnings: Vec<Warning>,
+}
+
+impl<'a> Analyzer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        let parser = Parser::new(input);
+
+        Analyzer {
+            parser,
+            warnings: vec![],
+        }
+    }
+
+    pub fn analyze_program(&mut self) -> Result<Vec<Warning>, ParserError> {
+        let program = self.parser.parse_program()?;
+
+        for statement in &program.0 {
+            self.analyze_statement(statement);
+        }
+
+        Ok(std::mem::take(&mut self.warnings))
+    }
+
+    fn analyze_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::VarStatement { name, value, .. } => {
+                if let Expression::FunctionExpression { body, .. } = value {
+                    if Self::has_non_tail_call(name, body, true) {
+                        self.warnings.push(Warning::NonTailRecursion(name.clone()));
+                    }
+                }
+
+                self.analyze_expression(value);
+            }
+            Statement::DestructuringStatement { value, .. }
+            | Statement::AssignStatement { value, .. } => self.analyze_expression(value),
+            Statement::IndexAssignStatement {
+                target,
+                index,
+                value,
+            } => {
+                self.analyze_expression(target);
+                self.analyze_expression(index);
+                self.analyze_expression(value);
+            }
+            Statement::ReturnStatement(expr) => {
+                if let Some(expr) = expr {
+                    self.analyze_expression(expr);
+                }
+            }
+            Statement::ExpressionStatement(expr) => self.analyze_expression(expr),
+            Statement::BlockStatement(statements) => {
+                for statement in statements {
+                    self.analyze_statement(statement);
+                }
+            }
+            Statement::ForStatement { iterable, body, .. } => {
+                self.analyze_expression(iterable);
+                self.analyze_statement(body);
+            }
+            Statement::BreakStatement | Statement::ContinueStatement => {}
+        }
+    }
+
+    fn analyze_expression(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Identifier(_)
+            | Expression::IntegerLiteral(_)
+            | Expression::BooleanLiteral(_)
+            | Expression::NilLiteral
+            | Expression::StringLiteral(_) => {}
+            Expression::ArrayLiteral(elements) | Expression::TupleLiteral(elements) => {
+                for element in elements {
+                    self.analyze_expression(element);
+                }
+            }
+            Expression::MapLiteral(map) => {
+                for (key, value) in map {
+                    self.analyze_expression(key);
+                    self.analyze_expression(value);
+                }
+            }
+            Expression::BinaryExpression {
+                left,
+                operator,
+                right,
+            } => {
+                if matches!(operator, TokenKind::Slash | TokenKind::Percentage)
+                    && Self::is_constant_zero(right)
+                {
+                    self.warnings.push(Warning::ZeroDenominator(expr.clone()));
+                }
+
+                self.analyze_expression(left);
+                self.analyze_expression(right);
+            }
+            Expression::UnaryExpression { value, .. } => self.analyze_expression(value),
+            Expression::IndexExpression { value, index } => {
+                self.analyze_expression(value);
+                self.analyze_expression(index);
+            }
+            Expression::SliceExpression { value, start, end } => {
+                self.analyze_expression(value);
+                for bound in [start, end].into_iter().flatten() {
+                    self.analyze_expression(bound);
+                }
+            }
+            Expression::GroupedExpression(expr) => self.analyze_expression(expr),
+            Expression::CallExpression { path, arguments } => {
+                self.analyze_expression(path);
+                for arg in arguments {
+                    self.analyze_expression(arg);
+                }
+            }
+            Expression::IfExpression {
+                condition,
+                consequence,
+                alternative,
+            } => {
+                self.analyze_expression(condition);
+                self.analyze_statement(consequence);
+                if let Some(alternative) = alternative {
+                    self.analyze_statement(alternative);
+                }
+            }
+            Expression::ConditionalExpression {
+                consequence,
+                condition,
+                alternative,
+            }
+            | Expression::TernaryExpression {
+                condition,
+                consequence,
+                alternative,
+            } => {
+                self.analyze_expression(consequence);
+                self.analyze_expression(condition);
+                self.analyze_expression(alternative);
+            }
+            Expression::FunctionExpression { body, .. } | Expression::DoExpression(body) => {
+                self.analyze_statement(body)
+            }
+            Expression::MatchExpression {
+                subject,
+                arms,
+                default,
+            } => {
+                self.analyze_expression(subject);
+                for (_, value) in arms {
+                    self.analyze_expression(value);
+                }
+                if let Some(default) = default {
+                    self.analyze_expression(default);
+                }
+            }
+        }
+    }
+
+    /// Whether `statement` calls the function `name` in a position whose result isn't
+    /// directly the result of the function, i.e. outside of a tail position.
+    /// `tail` tells if the value of `statement` is the result of the function.
+    fn has_non_tail_call(name: &str, statement: &Statement, tail: bool) -> bool {
+        match statement {
+            Statement::VarStatement { value, .. }
+            | Statement::DestructuringStatement { value, .. }
+            | Statement::AssignStatement { value, .. } => {
+                Self::has_non_tail_call_expr(name, value, false)
+            }
+            Statement::IndexAssignStatement {
+                target,
+                index,
+                value,
+            } => [target.as_ref(), index, value]
+                .iter()
+                .any(|expr| Self::has_non_tail_call_expr(name, expr, false)),
+            // returned values are always in tail position, even when returning early
+            Statement::ReturnStatement(expr) => expr
+                .as_ref()
+                .is_some_and(|expr| Self::has_non_tail_call_expr(name, expr, true)),
+            Statement::ExpressionStatement(expr) => Self::has_non_tail_call_expr(name, expr, tail),
+            Statement::BlockStatement(statements) => {
+                statements.iter().enumerate().any(|(i, statement)| {
+                    Self::has_non_tail_call(name, statement, tail && i == statements.len() - 1)
+                })
+            }
+            Statement::ForStatement { iterable, body, .. } => {
+                Self::has_non_tail_call_expr(name, iterable, false)
+                    || Self::has_non_tail_call(name, body, false)
+            }
+            Statement::BreakStatement | Statement::ContinueStatement => false,
+        }
+    }
+
+    fn has_non_tail_call_expr(name: &str, expr: &Expression, tail: bool) -> bool {
+        match expr {
+            Expression::Identifier(_)
+            | Expression::IntegerLiteral(_)
+            | Expression::BooleanLiteral(_)
+            | Expression::NilLiteral
+            | Expression::StringLiteral(_) => false,
+            Expression::ArrayLiteral(elements) | Expression::TupleLiteral(elements) => elements
+                .iter()
+                .any(|element| Self::has_non_tail_call_expr(name, element, false)),
+            Expression::MapLiteral(map) => map.iter().any(|(key, value)| {
+                Self::has_non_tail_call_expr(name, key, false)
+                    || Self::has_non_tail_call_expr(name, value, false)
+            }),
+            Expression::BinaryExpression { left, right, .. } => {
+                Self::has_non_tail_call_expr(name, left, false)
+                    || Self::has_non_tail_call_expr(name, right, false)
+            }
+            Expression::UnaryExpression { value, .. } => {
+                Self::has_non_tail_call_expr(name, value, false)
+            }
+            Expression::IndexExpression { value, index } => {
+                Self::has_non_tail_call_expr(name, value, false)
+                    || Self::has_non_tail_call_expr(name, index, false)
+            }
+            Expression::SliceExpression { value, start, end } => {
+                Self::has_non_tail_call_expr(name, value, false)
+                    || [start, end]
+                        .into_iter()
+                        .flatten()
+                        .any(|bound| Self::has_non_tail_call_expr(name, bound, false))
+            }
+            Expression::GroupedExpression(expr) => Self::has_non_tail_call_expr(name, expr, tail),
+            Expression::CallExpression { path, arguments } => {
+                (!tail && matches!(path.as_ref(), Expression::Identifier(path) if path == name))
+                    || Self::has_non_tail_call_expr(name, path, false)
+                    || arguments
+                        .iter()
+                        .any(|arg| Self::has_non_tail_call_expr(name, arg, false))
+            }
+            Expression::IfExpression {
+                condition,
+                consequence,
+                alternative,
+            } => {
+                Self::has_non_tail_call_expr(name, condition, false)
+                    || Self::has_non_tail_call(name, consequence, tail)
+                    || alternative
+                        .as_ref()
+                        .is_some_and(|alternative| Self::has_non_tail_call(name, alternative, tail))
+            }
+            Expression::ConditionalExpression {
+                consequence,
+                condition,
+                alternative,
+            }
+            | Expression::TernaryExpression {
+                condition,
+                consequence,
+                alternative,
+            } => {
+                Self::has_non_tail_call_expr(name, condition, false)
+                    || Self::has_non_tail_call_expr(name, consequence, tail)
+                    || Self::has_non_tail_call_expr(name, alternative, tail)
+            }
+            Expression::DoExpression(body) => Self::has_non_tail_call(name, body, tail),
+            // patterns are literals, so only the subject and the values can call anything
+            Expression::MatchExpression {
+                subject,
+                arms,
+                default,
+            } => {
+                Self::has_non_tail_call_expr(name, subject, false)
+                    || arms
+                        .iter()
+                        .map(|(_, value)| value)
+                        .chain(default.as_deref())
+                        .any(|value| Self::has_non_tail_call_expr(name, value, tail))
+            }
+            // calls inside nested functions belong to a different call frame
+            Expression::FunctionExpression { .. } => false,
+        }
+    }
+
+    /// Matches `0`, also when it's grouped or negated (e.g. `(-0)`).
+    fn is_constant_zero(expr: &Expression) -> bool {
+        match expr {
+            Expression::IntegerLiteral(value) => *value == 0,
+            Expression::GroupedExpression(expr) => Self::is_constant_zero(expr),
+            Expression::UnaryExpression {
+                operator: TokenKind::Minus,
+                value,
+            } => Self::is_constant_zero(value),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn analyze_zero_denominator() {
+        let input = r#"
+            let a = 5 / 0;
+            let b = fn(x) { x % (-0) };
+            let c = 5 / x;
+            let d = 5 / (1 - 1);
+        "#;
+
+        let mut analyzer = Analyzer::new(input);
+        let warnings = analyzer.analyze_program().unwrap();
+
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(
+            warnings[0].to_string(),
+            "`(5 / 0)` has a zero denominator and will always fail"
+        );
+        assert_eq!(
+            warnings[1].to_string(),
+            "`(x % (-0))` has a zero denominator and will always fail"
+        );
+    }
+
+    #[test]
+    fn analyze_non_tail_recursion() {
+        let input = r#"
+            let f = fn(n) { if n == 0 { 0 } else { 1 + f(n - 1) } };
+            let g = fn(n, acc) { if n == 0 { acc } else { g(n - 1, acc + 1) } };
+            let h = fn(n) { if n == 0 { return 0; }; let x = h(n - 1); x };
+            let i = fn(n) { return i(n - 1) if n > 0 else 0; };
+            let k = fn(n) { n > 0 ? k(n - 1) : 0 };
+            let l = fn(n) { match n { 0 => 0, _ => l(n - 1) } };
+            let m = fn(n) { match m(n) { _ => 0 } };
+            let j = fn(n) { j(j(n)) };
+        "#;
+
+        let mut analyzer = Analyzer::new(input);
+        let warnings = analyzer.analyze_program().unwrap();
+
+        assert_eq!(
+            warnings,
+            vec![
+                Warning::NonTailRecursion("f".to_owned()),
+                Warning::NonTailRecursion("h".to_owned()),
+                Warning::NonTailRecursion("m".to_owned()),
+                Warning::NonTailRecursion("j".to_owned()),
+            ]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "`f` calls itself outside of a tail position, so deep recursions grow the stack"
+        );
+    }
+}
@@ -1,5 +1,4 @@
 use std::{
-    collections::HashMap,
     fmt,
     num::{ParseIntError, TryFromIntError},
     rc::Rc,
@@ -23,13 +22,20 @@ impl fmt::Display for Program {
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Statement {
-    // TODO: support different types of var statements
+    /// `let` and `const` bindings, the latter cannot be re-assigned.
     VarStatement {
         kind: TokenKind,
         name: String,
         value: Expression,
     },
 
+    /// Binds the elements of an array to multiple names, e.g. `let [a, b] = f();`.
+    DestructuringStatement {
+        kind: TokenKind,
+        names: Vec<String>,
+        value: Expression,
+    },
+
     ReturnStatement(Option<Expression>),
 
     AssignStatement {
@@ -37,9 +43,27 @@ pub enum Statement {
         value: Expression,
     },
 
+    /// Assignment to an element of an array or a map, e.g. `arr[0] = 1;`.
+    /// The target is either an identifier or another index expression (`a[0][1] = 1;`).
+    IndexAssignStatement {
+        target: Box<Expression>,
+        index: Box<Expression>,
+        value: Expression,
+    },
+
     ExpressionStatement(Expression),
 
     BlockStatement(Vec<Statement>),
+
+    ForStatement {
+        var: String,
+        iterable: Expression,
+        body: Box<Statement>,
+    },
+
+    BreakStatement,
+
+    ContinueStatement,
 }
 
 impl fmt::Display for Statement {
@@ -48,6 +72,9 @@ impl fmt::Display for Statement {
             Statement::VarStatement { kind, name, value } => {
                 write!(f, "{} {} = {};", kind, name, value)
             }
+            Statement::DestructuringStatement { kind, names, value } => {
+                write!(f, "{} [{}] = {};", kind, names.join(", "), value)
+            }
             Statement::ReturnStatement(expr) => {
                 if let Some(expr) = expr {
                     write!(f, "return {expr};")
@@ -56,6 +83,11 @@ impl fmt::Display for Statement {
                 }
             }
             Statement::AssignStatement { name, value } => write!(f, "{name} = {value};"),
+            Statement::IndexAssignStatement {
+                target,
+                index,
+                value,
+            } => write!(f, "{target}[{index}] = {value};"),
             Statement::ExpressionStatement(expr) => write!(f, "{expr}"),
             Statement::BlockStatement(statements) => {
                 write!(f, "{{")?;
@@ -64,6 +96,13 @@ impl fmt::Display for Statement {
                 }
                 write!(f, "}}")
             }
+            Statement::ForStatement {
+                var,
+                iterable,
+                body,
+            } => write!(f, "for {var} in {iterable} {body}"),
+            Statement::BreakStatement => write!(f, "break;"),
+            Statement::ContinueStatement => write!(f, "continue;"),
         }
     }
 }
@@ -76,12 +115,20 @@ pub enum Expression {
 
     BooleanLiteral(bool),
 
+    /// The explicit absence of a value, `nil`.
+    NilLiteral,
+
     StringLiteral(String),
 
     ArrayLiteral(Vec<Expression>),
 
-    // TODO: support different types of keys, as long as they are hashable.
-    MapLiteral(HashMap<String, Expression>),
+    /// Comma-separated expressions inside parentheses, e.g. `(1, 2)`.
+    /// They are evaluated like arrays, except for `()` which is the unit value.
+    TupleLiteral(Vec<Expression>),
+
+    /// Key-value pairs in the order they were written, e.g. `{"a": 1, 2: true}`.
+    /// Keys are evaluated too, and must result in hashable objects.
+    MapLiteral(Vec<(Expression, Expression)>),
 
     BinaryExpression {
         left: Box<Expression>,
@@ -99,6 +146,13 @@ pub enum Expression {
         index: Box<Expression>,
     },
 
+    /// Half-open range of an array or a string, e.g. `arr[1:3]`, whose bounds can be omitted.
+    SliceExpression {
+        value: Box<Expression>,
+        start: Option<Box<Expression>>,
+        end: Option<Box<Expression>>,
+    },
+
     GroupedExpression(Box<Expression>),
 
     CallExpression {
@@ -116,6 +170,31 @@ pub enum Expression {
         parameters: Vec<String>,
         body: Box<Statement>,
     },
+
+    /// Python-style conditional, e.g. `a if cond else b`.
+    ConditionalExpression {
+        consequence: Box<Expression>,
+        condition: Box<Expression>,
+        alternative: Box<Expression>,
+    },
+
+    /// C-style conditional, e.g. `cond ? a : b`.
+    TernaryExpression {
+        condition: Box<Expression>,
+        consequence: Box<Expression>,
+        alternative: Box<Expression>,
+    },
+
+    /// Picks the value of the first arm whose literal pattern equals the subject, e.g.
+    /// `match n { 1 => "one", 2 => "two", _ => "many" }`, falling back to the `_` arm.
+    MatchExpression {
+        subject: Box<Expression>,
+        arms: Vec<(Expression, Expression)>,
+        default: Option<Box<Expression>>,
+    },
+
+    /// A block evaluated in its own scope, whose value is the value of its last statement.
+    DoExpression(Box<Statement>),
 }
 
 impl fmt::Display for Expression {
@@ -124,6 +203,7 @@ impl fmt::Display for Expression {
             Expression::Identifier(s) => write!(f, "{s}"),
             Expression::IntegerLiteral(n) => write!(f, "{n}"),
             Expression::BooleanLiteral(b) => write!(f, "{b}"),
+            Expression::NilLiteral => write!(f, "nil"),
             Expression::StringLiteral(s) => write!(f, "\"{s}\""),
             Expression::ArrayLiteral(elements) => {
                 write!(f, "[")?;
@@ -135,13 +215,27 @@ impl fmt::Display for Expression {
                 }
                 write!(f, "]")
             }
+            Expression::TupleLiteral(elements) => {
+                write!(f, "(")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{element}")?;
+                }
+                // keep single-element tuples distinct from grouped expressions
+                if elements.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
             Expression::MapLiteral(map) => {
                 write!(f, "{{")?;
                 for (i, (key, value)) in map.iter().enumerate() {
                     if i > 0 {
                         write!(f, ", ")?;
                     }
-                    write!(f, "\"{key}\": {value}")?;
+                    write!(f, "{key}: {value}")?;
                 }
                 write!(f, "}}")
             }
@@ -158,6 +252,17 @@ impl fmt::Display for Expression {
             Expression::IndexExpression { value, index } => {
                 write!(f, "({value}[{index}])")
             }
+            Expression::SliceExpression { value, start, end } => {
+                write!(f, "({value}[")?;
+                if let Some(start) = start {
+                    write!(f, "{start}")?;
+                }
+                write!(f, ":")?;
+                if let Some(end) = end {
+                    write!(f, "{end}")?;
+                }
+                write!(f, "])")
+            }
             Expression::GroupedExpression(expr) => write!(f, "{expr}"),
             Expression::CallExpression { path, arguments } => {
                 write!(f, "{path}(")?;
@@ -185,6 +290,41 @@ impl fmt::Display for Expression {
                 }
             }
 
+            Expression::ConditionalExpression {
+                consequence,
+                condition,
+                alternative,
+            } => write!(f, "({consequence} if {condition} else {alternative})"),
+
+            Expression::TernaryExpression {
+                condition,
+                consequence,
+                alternative,
+            } => write!(f, "({condition} ? {consequence} : {alternative})"),
+
+            Expression::DoExpression(body) => write!(f, "do {body}"),
+
+            Expression::MatchExpression {
+                subject,
+                arms,
+                default,
+            } => {
+                write!(f, "match {subject} {{")?;
+                for (i, (pattern, value)) in arms.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{pattern} => {value}")?;
+                }
+                if let Some(default) = default {
+                    if !arms.is_empty() {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "_ => {default}")?;
+                }
+                write!(f, "}}")
+            }
+
             Expression::FunctionExpression { parameters, body } => {
                 write!(f, "fn(")?;
                 for (i, param) in parameters.iter().enumerate() {
@@ -207,6 +347,9 @@ pub enum ParserError {
     #[error("Unexpected token: {0:#?}")]
     UnexpectedToken(Rc<Token>),
 
+    #[error("'{0}' is a reserved keyword and cannot be used as a variable name")]
+    ReservedKeyword(String),
+
     #[error("Operator received an invalid operand type: {0:#?}")]
     InvalidOperandType(Rc<Token>),
 
@@ -1,11 +1,19 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
 use crate::object::{EvalError, Object};
 
 #[derive(Debug, Default, PartialEq, Eq, Clone)]
 pub struct Environment {
     pub store: HashMap<String, Object>,
+    /// Names of the bindings of `store` that cannot be re-assigned.
+    pub constants: HashSet<String>,
     pub outer: Option<Rc<RefCell<Environment>>>,
+    /// Frozen environments reject any new binding, e.g. a prelude shared between forked evaluators.
+    pub frozen: bool,
 }
 
 impl Environment {
@@ -19,7 +27,37 @@ impl Environment {
         }
     }
 
-    pub fn set(&mut self, name: String, value: Object) {
+    /// Binds a mutable value, shadowing any previous binding with the same name.
+    pub fn set(&mut self, name: String, value: Object) -> Result<(), EvalError> {
+        if self.frozen {
+            return Err(EvalError::FrozenEnvironment(name));
+        }
+
+        self.constants.remove(&name);
         self.store.insert(name, value);
+        Ok(())
+    }
+
+    /// Binds a value that cannot be re-assigned, although it can still be shadowed.
+    pub fn set_const(&mut self, name: String, value: Object) -> Result<(), EvalError> {
+        self.set(name.clone(), value)?;
+        self.constants.insert(name);
+        Ok(())
+    }
+
+    /// Re-binds `name` in the environment where it was declared, unlike `set`
+    /// which always binds it in this environment.
+    pub fn assign(&mut self, name: String, value: Object) -> Result<(), EvalError> {
+        if self.store.contains_key(&name) {
+            if self.constants.contains(&name) {
+                return Err(EvalError::AssignmentToImmutable(name));
+            }
+
+            self.set(name, value)
+        } else if let Some(outer) = &self.outer {
+            outer.borrow_mut().assign(name, value)
+        } else {
+            Err(EvalError::IdentifierNotFound(name))
+        }
     }
 }
@@ -1,17 +1,218 @@
-use std::{cell::RefCell, collections::HashMap, fmt::Debug, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashSet},
+    fmt::{self, Debug},
+    io::{self, Write},
+    ops::ControlFlow,
+    panic::{self, AssertUnwindSafe},
+    rc::Rc,
+    time::Instant,
+};
 
 use crate::{
-    ast::{Expression, ParserError, Statement},
+    ast::{Expression, Program, Statement},
     environment::Environment,
-    object::{BuiltinFunction, Closure, EvalError, Object},
+    object::{
+        BuiltinFunction, Closure, EvalError, FunctionCell, FunctionRef, HashableObject, Memoized,
+        NativeFunction, Object,
+    },
     parser::Parser,
     token::TokenKind,
 };
 
-#[derive(Debug)]
+mod builtins;
+
+/// Default number of nested function calls allowed before giving up. Each of them can take
+/// tens of KiB of native stack in debug builds, more when going through builtin callbacks
+/// (e.g. `map`), so the default fits the 8 MiB stack of a main thread in any case.
+pub const DEFAULT_MAX_DEPTH: usize = 100;
+
+/// Default number of elements (or bytes, for strings) that arrays and strings can grow to,
+/// to avoid exhausting memory by mistake.
+pub const DEFAULT_MAX_COLLECTION_SIZE: usize = 1 << 24;
+
+/// Number of statements evaluated between two checks of the deadline, since reading the clock isn't free.
+const DEADLINE_CHECK_INTERVAL: usize = 1024;
+
+/// Renders an error given the source of the program that raised it.
+pub type ErrorFormatter = Box<dyn Fn(&EvalError, &str) -> String>;
+
 pub struct Evaluator<'a> {
+    input: &'a str,
+    /// Source given to the last call of `eval_str`, which errors are rendered against
+    /// until `eval_program` evaluates `input` again.
+    source: Option<String>,
     parser: Parser<'a>,
     env: Rc<RefCell<Environment>>,
+    /// Number of function calls currently being evaluated.
+    depth: usize,
+    max_depth: usize,
+    max_collection_size: usize,
+    /// Time after which the evaluation is stopped, if any.
+    deadline: Option<Instant>,
+    /// Number of statements evaluated so far, to check the deadline every now and then.
+    steps: usize,
+    /// Flags set by the embedder, which programs can check through `cfg`.
+    flags: HashSet<String>,
+    /// Whether using `()` as an operand is reported with a dedicated error.
+    strict: bool,
+    /// Whether conditions accept any value, based on `Object::is_truthy`.
+    truthiness: bool,
+    /// Shared with forks, so that they render errors the same way.
+    error_formatter: Rc<ErrorFormatter>,
+    /// Where `print`, `println` and `debug` write, shared with forks.
+    out: Rc<RefCell<dyn Write>>,
+    /// Where `eprint` and `eprintln` write, shared with forks.
+    err: Rc<RefCell<dyn Write>>,
+}
+
+impl Debug for Evaluator<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Evaluator")
+            .field("input", &self.input)
+            .field("source", &self.source)
+            .field("parser", &self.parser)
+            .field("env", &self.env)
+            .field("depth", &self.depth)
+            .field("max_depth", &self.max_depth)
+            .field("max_collection_size", &self.max_collection_size)
+            .field("deadline", &self.deadline)
+            .field("flags", &self.flags)
+            .field("strict", &self.strict)
+            .field("truthiness", &self.truthiness)
+            .finish_non_exhaustive()
+    }
+}
+
+fn write_to(writer: &RefCell<dyn Write>, output: fmt::Arguments) -> Result<(), EvalError> {
+    writer
+        .borrow_mut()
+        .write_fmt(output)
+        .map_err(|err| EvalError::OutputFailed(err.to_string()))
+}
+
+/// Whether `obj` is a loop control, which must be passed on to the enclosing loop
+/// instead of being used as a value (e.g. bound by `let` or passed to a function).
+fn is_loop_control(obj: &Object) -> bool {
+    matches!(obj, Object::BreakValue | Object::ContinueValue)
+}
+
+/// Evaluates `expr` as an operand of a larger expression, which is stopped by loop controls:
+/// they're given back right away by the enclosing function, to reach the enclosing loop.
+macro_rules! eval_operand {
+    ($evaluator:expr, $expr:expr) => {
+        match $evaluator.eval_expression($expr, false)? {
+            obj if is_loop_control(&obj) => return Ok(obj),
+            obj => obj,
+        }
+    };
+}
+
+/// Converts an index into a position of a structure with `len` elements.
+/// Negative indices count from the end, so `-1` is the last element and `-len` the first one.
+fn resolve_index(index: i32, len: usize) -> Result<usize, EvalError> {
+    let position = if index < 0 {
+        len.checked_sub(index.unsigned_abs() as usize)
+    } else {
+        Some(index as usize)
+    };
+
+    position
+        .filter(|position| *position < len)
+        .ok_or(EvalError::IndexOutOfBounds(len, index))
+}
+
+/// Gets the element of `value` at `index`, as done by the index operator.
+fn index_object(value: Object, index: Object) -> Result<Object, EvalError> {
+    match value {
+        Object::ArrayValue(objects) => {
+            if let Object::IntegerValue(index) = index {
+                let id = resolve_index(index, objects.len())?;
+
+                Ok(objects[id].clone())
+            } else {
+                Err(EvalError::InvalidIndexType)
+            }
+        }
+        Object::BytesValue(bytes) => {
+            if let Object::IntegerValue(index) = index {
+                let id = resolve_index(index, bytes.len())?;
+
+                Ok(Object::IntegerValue(i32::from(bytes[id])))
+            } else {
+                Err(EvalError::InvalidIndexType)
+            }
+        }
+        // strings are indexed by characters, not by bytes
+        Object::StringValue(text) => {
+            if let Object::IntegerValue(index) = index {
+                let id = resolve_index(index, text.chars().count())?;
+
+                // unwrapping is fine, the index was checked against the number of characters
+                Ok(Object::StringValue(
+                    text.chars().nth(id).unwrap().to_string(),
+                ))
+            } else {
+                Err(EvalError::InvalidIndexType)
+            }
+        }
+        Object::MapValue(map) => {
+            if let Ok(key) = HashableObject::try_from(index) {
+                let item = map
+                    .get(&key)
+                    .ok_or_else(|| EvalError::ValueNotFound(key.to_string()))?;
+
+                Ok(item.clone())
+            } else {
+                Err(EvalError::InvalidIndexType)
+            }
+        }
+        _ => Err(EvalError::InvalidIndexUsage),
+    }
+}
+
+/// Finds the method overriding `operator` when both operands are maps, e.g. `__add__` for `+`.
+/// The method is looked up in the left operand, and it's called with both operands.
+fn operator_method(left: &Object, right: &Object, operator: &TokenKind) -> Option<Object> {
+    let (Object::MapValue(map), Object::MapValue(_)) = (left, right) else {
+        return None;
+    };
+
+    let name = match operator {
+        TokenKind::Plus => "__add__",
+        TokenKind::Minus => "__sub__",
+        TokenKind::Asterisk => "__mul__",
+        TokenKind::Slash => "__div__",
+        TokenKind::Percentage => "__mod__",
+        _ => return None,
+    };
+
+    map.get(&HashableObject::String(name.to_owned()))
+        .filter(|method| method.is_callable())
+        .cloned()
+}
+
+/// Converts the bounds of a slice into a range of a structure with `len` elements.
+/// Omitted bounds select from the start or up to the end, negative bounds count from the end,
+/// and bounds are clamped to the structure, so slices out of range are just empty.
+fn slice_range(start: Option<i32>, end: Option<i32>, len: usize) -> std::ops::Range<usize> {
+    let resolve = |bound: i32| {
+        if bound < 0 {
+            len.saturating_sub(bound.unsigned_abs() as usize)
+        } else {
+            (bound as usize).min(len)
+        }
+    };
+
+    let start = start.map_or(0, resolve);
+    let end = end.map_or(len, resolve);
+
+    start..end.max(start)
+}
+
+/// Renders errors in the `| Qalo Error |` style used by the command line.
+fn default_error_formatter(err: &EvalError, _input: &str) -> String {
+    format!("| Qalo Error |\n{err}")
 }
 
 impl<'a> Evaluator<'a> {
@@ -19,21 +220,244 @@ impl<'a> Evaluator<'a> {
         let parser = Parser::new(input);
         let env = Rc::new(RefCell::new(Environment::default()));
 
-        Evaluator { parser, env }
+        Evaluator {
+            input,
+            source: None,
+            parser,
+            env,
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_collection_size: DEFAULT_MAX_COLLECTION_SIZE,
+            deadline: None,
+            steps: 0,
+            flags: HashSet::new(),
+            strict: false,
+            truthiness: false,
+            error_formatter: Rc::new(Box::new(default_error_formatter)),
+            out: Rc::new(RefCell::new(io::stdout())),
+            err: Rc::new(RefCell::new(io::stderr())),
+        }
+    }
+
+    /// Sets how many nested function calls are allowed before failing with
+    /// `EvalError::RecursionLimitExceeded`, instead of overflowing the native stack.
+    /// Every call consumes native stack, so a higher limit may need a bigger thread stack.
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// Sets how large arrays and strings (in bytes) can grow through `append`, `+`, `repeat`,
+    /// `range`, `window`, `fit`, `join`, `template` and `str` before failing with
+    /// `EvalError::CollectionTooLarge`, e.g. to bound the memory used by untrusted programs.
+    pub fn set_max_collection_size(&mut self, max_collection_size: usize) {
+        self.max_collection_size = max_collection_size;
+    }
+
+    /// Stops the evaluation with `EvalError::Timeout` once `deadline` has passed, e.g. to bound
+    /// the time spent by scripts run inside a request handler. The clock is only read every
+    /// few statements, so a single long-running built-in call can overrun the deadline.
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        self.deadline = Some(deadline);
+    }
+
+    /// Fails once the deadline has passed, checking the clock every `DEADLINE_CHECK_INTERVAL` calls.
+    fn check_deadline(&mut self) -> Result<(), EvalError> {
+        let Some(deadline) = self.deadline else {
+            return Ok(());
+        };
+
+        self.steps = self.steps.wrapping_add(1);
+        if self.steps.is_multiple_of(DEADLINE_CHECK_INTERVAL) && Instant::now() >= deadline {
+            return Err(EvalError::Timeout);
+        }
+
+        Ok(())
+    }
+
+    /// Fails when a collection of `size` elements would exceed the maximum collection size.
+    fn check_collection_size(&self, size: usize) -> Result<(), EvalError> {
+        if size > self.max_collection_size {
+            return Err(EvalError::CollectionTooLarge(self.max_collection_size));
+        }
+
+        Ok(())
+    }
+
+    /// Sets a flag that programs can check with `cfg(flag)`, e.g. `"debug"` or `"verbose"`.
+    pub fn set_flag(&mut self, flag: impl Into<String>) {
+        self.flags.insert(flag.into());
+    }
+
+    /// Enables the strict mode, where operations on `()` (e.g. the result of a function
+    /// that returned no value) fail with `EvalError::MissingValue` instead of a generic error.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Enables truthiness in conditions, so that non-boolean values are accepted too:
+    /// `0`, `nil`, `()` and empty collections are false, other values are true.
+    pub fn set_truthiness(&mut self, truthiness: bool) {
+        self.truthiness = truthiness;
+    }
+
+    /// Binds `name` to a function that can be redefined later on, e.g. by a REPL.
+    /// The first call creates a shared cell holding the function, following calls replace
+    /// its content: every reference to the cell, like aliases (`let g = f;`) or closures
+    /// calling `f`, then sees the new definition. Bindings made by programs themselves
+    /// (e.g. `let f = fn() {...};`) still create independent values.
+    pub fn redefine(&mut self, name: impl Into<String>, function: Object) -> Result<(), EvalError> {
+        let name = name.into();
+
+        let function = match function {
+            // store the current definition, a cell holding itself could never be called
+            Object::RedefinableValue(cell) => cell.function.borrow().clone(),
+            function if function.is_callable() => function,
+            other => {
+                return Err(EvalError::TypeMismatch(format!(
+                    "only functions can be redefined, got {}",
+                    other.type_name()
+                )))
+            }
+        };
+
+        let existing = self.env.borrow().get(&name);
+        if let Ok(Object::RedefinableValue(cell)) = existing {
+            *cell.function.borrow_mut() = function;
+            return Ok(());
+        }
+
+        let cell = FunctionCell {
+            name: name.clone(),
+            function: Rc::new(RefCell::new(function)),
+        };
+        self.env
+            .borrow_mut()
+            .set(name, Object::RedefinableValue(cell))
+    }
+
+    /// Binds a function implemented in Rust, which programs can call like any other function.
+    ///
+    /// Panics inside the function are caught and returned as `EvalError::NativePanic`,
+    /// so a faulty native doesn't bring down the embedder. Since the function is treated
+    /// as unwind safe, state it shares (e.g. through `RefCell`s) could be left inconsistent
+    /// by a panic: don't rely on such state afterwards. Panics still go through the panic hook,
+    /// which prints them to stderr by default, and they can't be caught with `panic = "abort"`.
+    pub fn register_native(
+        &mut self,
+        name: impl Into<String>,
+        function: impl Fn(Vec<Object>) -> Result<Object, EvalError> + 'static,
+    ) -> Result<(), EvalError> {
+        let name = name.into();
+        let native = NativeFunction {
+            name: name.clone(),
+            function: Rc::new(function),
+        };
+
+        self.env.borrow_mut().set(name, Object::NativeValue(native))
+    }
+
+    /// Names of all built-in functions, e.g. for autocompletion.
+    pub fn builtin_names() -> Vec<&'static str> {
+        BuiltinFunction::NAMES.to_vec()
+    }
+
+    /// Sets how errors are rendered by `render_error`, e.g. as JSON for an API.
+    /// The formatter receives the error and the source of the program.
+    pub fn set_error_formatter(&mut self, formatter: ErrorFormatter) {
+        self.error_formatter = Rc::new(formatter);
+    }
+
+    /// Sets where programs write through `print`, `println` and `debug`, e.g. a buffer
+    /// to capture their output. Defaults to the standard output.
+    pub fn set_writer(&mut self, writer: impl Write + 'static) {
+        self.out = Rc::new(RefCell::new(writer));
+    }
+
+    /// Sets both where programs write through `print`, `println` and `debug`,
+    /// and where they write through `eprint` and `eprintln`.
+    /// Defaults to the standard output and the standard error.
+    pub fn with_writers(mut self, out: impl Write + 'static, err: impl Write + 'static) -> Self {
+        self.out = Rc::new(RefCell::new(out));
+        self.err = Rc::new(RefCell::new(err));
+        self
+    }
+
+    /// Writes the output of a program to the writer of the evaluator.
+    fn write_output(&self, output: fmt::Arguments) -> Result<(), EvalError> {
+        write_to(&self.out, output)
+    }
+
+    /// Writes the error output of a program to the error writer of the evaluator.
+    fn write_error_output(&self, output: fmt::Arguments) -> Result<(), EvalError> {
+        write_to(&self.err, output)
+    }
+
+    /// Renders an error raised by this evaluator with its error formatter,
+    /// against the source that was evaluated last.
+    pub fn render_error(&self, err: &EvalError) -> String {
+        let source = self.source.as_deref().unwrap_or(self.input);
+        (self.error_formatter.as_ref())(err, source)
+    }
+
+    /// Creates a new evaluator for `input` whose scope is a child of the current environment.
+    /// The current environment is frozen, so it can be shared as a prelude between forks:
+    /// bindings made by a fork stay isolated from the other forks and from the prelude itself.
+    pub fn fork<'b>(&self, input: &'b str) -> Evaluator<'b> {
+        self.env.borrow_mut().frozen = true;
+
+        let parser = Parser::new(input);
+        let env = Rc::new(RefCell::new(Environment {
+            outer: Some(self.env.clone()),
+            ..Default::default()
+        }));
+
+        Evaluator {
+            input,
+            source: None,
+            parser,
+            env,
+            depth: 0,
+            max_depth: self.max_depth,
+            max_collection_size: self.max_collection_size,
+            deadline: self.deadline,
+            steps: 0,
+            flags: self.flags.clone(),
+            strict: self.strict,
+            truthiness: self.truthiness,
+            error_formatter: self.error_formatter.clone(),
+            out: self.out.clone(),
+            err: self.err.clone(),
+        }
     }
 
     pub fn eval_program(&mut self) -> Result<Vec<Object>, EvalError> {
+        self.source = None;
         let program = self.parser.parse_program()?;
+        self.eval_statements(program)
+    }
+
+    /// Evaluates another program in the current environment, so that it can use
+    /// the bindings made so far, e.g. for the successive inputs of a REPL.
+    /// Errors raised by `source` are rendered against it, so it's kept until the next evaluation.
+    pub fn eval_str(&mut self, source: &str) -> Result<Vec<Object>, EvalError> {
+        self.source = Some(source.to_owned());
+        let program = Parser::new(source).parse_program()?;
+        self.eval_statements(program)
+    }
+
+    fn eval_statements(&mut self, program: Program) -> Result<Vec<Object>, EvalError> {
         let mut objects: Vec<Object> = vec![];
 
         for statement in program.0 {
             let obj = self.eval_statement(statement)?;
 
-            // unwrap top-level return values
-            if let Object::ReturnValue(inner_obj) = obj {
-                objects.push(*inner_obj);
-            } else {
-                objects.push(obj);
+            match obj {
+                // unwrap top-level return values
+                Object::ReturnValue(inner_obj) => objects.push(*inner_obj),
+                // loop controls that escaped every loop
+                Object::BreakValue => return Err(EvalError::BreakOutsideLoop),
+                Object::ContinueValue => return Err(EvalError::ContinueOutsideLoop),
+                obj => objects.push(obj),
             }
         }
 
@@ -41,70 +465,169 @@ impl<'a> Evaluator<'a> {
     }
 
     fn eval_statement(&mut self, statement: Statement) -> Result<Object, EvalError> {
+        // loops and calls all go through statements, so no program can dodge the deadline
+        self.check_deadline()?;
+
         match statement {
-            Statement::VarStatement {
-                kind: _,
-                name,
-                value,
-            } => {
+            Statement::VarStatement { kind, name, value } => {
                 let obj = self.eval_expression(value, true)?;
-                self.env.borrow_mut().set(name, obj);
+                if is_loop_control(&obj) {
+                    return Ok(obj);
+                }
+
+                self.bind(&kind, name, obj)?;
                 Ok(Object::UnitValue)
             }
+            Statement::DestructuringStatement { kind, names, value } => {
+                self.eval_destructuring_statement(kind, names, value)
+            }
             Statement::ReturnStatement(_) => {
                 // return statements aren't allowed at the top-level scope
-                return Err(EvalError::ReturnOutsideExpression);
+                Err(EvalError::ReturnOutsideExpression)
             }
             Statement::AssignStatement { name, value } => {
                 let obj = self.eval_expression(value, true)?;
-                self.env.borrow_mut().set(name, obj);
+                if is_loop_control(&obj) {
+                    return Ok(obj);
+                }
+
+                self.env.borrow_mut().assign(name, obj)?;
                 Ok(Object::UnitValue)
             }
+            Statement::IndexAssignStatement {
+                target,
+                index,
+                value,
+            } => self.eval_index_assign_statement(*target, *index, value),
             Statement::ExpressionStatement(expr) => Ok(self.eval_expression(expr, true)?),
-            Statement::BlockStatement(statements) => {
-                let inner_env = self.create_enclosed_env();
-                let outer_env = std::mem::replace(&mut self.env, inner_env);
-
-                // save last evaluated object
-                let mut obj = Object::UnitValue;
-
-                for statement in statements {
-                    // handle return statements inside a block
-                    if let Statement::ReturnStatement(expr) = statement {
-                        let expr_eval = if let Some(expr) = expr {
-                            self.eval_expression(expr, true)?
-                        } else {
-                            Object::UnitValue
-                        };
+            Statement::BlockStatement(statements) => self.eval_block_statement(statements),
+            Statement::ForStatement {
+                var,
+                iterable,
+                body,
+            } => self.eval_for_statement(var, iterable, *body),
+            Statement::BreakStatement => Ok(Object::BreakValue),
+            Statement::ContinueStatement => Ok(Object::ContinueValue),
+        }
+    }
 
-                        // if the result of the evaluation is a *return value*, keep it to
-                        // propagate it to upper blocks...
-                        if matches!(expr_eval, Object::ReturnValue(_)) {
-                            obj = expr_eval;
-                        } else {
-                            // ...otherwise, wrap the value inside a *return value*
-                            obj = Object::ReturnValue(Box::new(expr_eval));
-                        }
+    /// Binds `name` in the current environment as declared by `kind` (`let` or `const`).
+    fn bind(&mut self, kind: &TokenKind, name: String, obj: Object) -> Result<(), EvalError> {
+        let mut env = self.env.borrow_mut();
 
-                        break;
-                    }
+        if *kind == TokenKind::Const {
+            env.set_const(name, obj)
+        } else {
+            env.set(name, obj)
+        }
+    }
 
-                    // evaluate all other types of statements
-                    obj = self.eval_statement(statement)?;
+    fn eval_destructuring_statement(
+        &mut self,
+        kind: TokenKind,
+        names: Vec<String>,
+        value: Expression,
+    ) -> Result<Object, EvalError> {
+        let values = match self.eval_expression(value, true)? {
+            Object::ArrayValue(values) => values,
+            obj if is_loop_control(&obj) => return Ok(obj),
+            other => {
+                return Err(EvalError::TypeMismatch(format!(
+                    "`{other}` cannot be destructured, only arrays can"
+                )))
+            }
+        };
 
-                    // if the current object is a *return value*, stop evaluating this block
-                    if let Object::ReturnValue(_) = obj {
-                        break;
-                    }
+        if values.len() != names.len() {
+            return Err(EvalError::DestructuringMismatch(names.len(), values.len()));
+        }
+
+        for (name, obj) in names.into_iter().zip(values) {
+            self.bind(&kind, name, obj)?;
+        }
+        Ok(Object::UnitValue)
+    }
+
+    fn eval_block_statement(&mut self, statements: Vec<Statement>) -> Result<Object, EvalError> {
+        let inner_env = self.create_enclosed_env();
+        let outer_env = std::mem::replace(&mut self.env, inner_env);
+
+        // save last evaluated object
+        let mut obj = Object::UnitValue;
+
+        for statement in statements {
+            // handle return statements inside a block
+            if let Statement::ReturnStatement(expr) = statement {
+                let expr_eval = if let Some(expr) = expr {
+                    self.eval_expression(expr, true)?
+                } else {
+                    Object::UnitValue
+                };
+
+                // if the result of the evaluation is a *return value* or a loop control,
+                // keep it to propagate it to upper blocks...
+                if matches!(expr_eval, Object::ReturnValue(_)) || is_loop_control(&expr_eval) {
+                    obj = expr_eval;
+                } else {
+                    // ...otherwise, wrap the value inside a *return value*
+                    obj = Object::ReturnValue(Box::new(expr_eval));
                 }
 
-                // go back to the outer environment
-                self.env = outer_env;
+                break;
+            }
+
+            // evaluate all other types of statements
+            obj = self.eval_statement(statement)?;
+
+            // if the current object is a *return value* or a loop control,
+            // stop evaluating this block
+            if matches!(obj, Object::ReturnValue(_)) || is_loop_control(&obj) {
+                break;
+            }
+        }
+
+        // go back to the outer environment
+        self.env = outer_env;
+
+        // return the last evaluated object
+        Ok(obj)
+    }
+
+    fn eval_for_statement(
+        &mut self,
+        var: String,
+        iterable: Expression,
+        body: Statement,
+    ) -> Result<Object, EvalError> {
+        let items = match eval_operand!(self, iterable) {
+            Object::ArrayValue(objects) => objects,
+            // maps are sorted, so their keys are iterated in a predictable order
+            Object::MapValue(map) => map.into_keys().map(Object::from).collect(),
+            other => {
+                return Err(EvalError::TypeMismatch(format!(
+                    "`{other}` cannot be iterated, only arrays and maps can"
+                )))
+            }
+        };
+
+        for item in items {
+            // every iteration binds the loop variable in its own environment
+            let loop_env = self.create_enclosed_env();
+            loop_env.borrow_mut().set(var.clone(), item)?;
+            let outer_env = std::mem::replace(&mut self.env, loop_env);
 
-                // return the last evaluated object
-                Ok(obj)
+            let obj = self.eval_statement(body.clone());
+            self.env = outer_env;
+
+            match obj? {
+                // stop looping when the body returns
+                obj @ Object::ReturnValue(_) => return Ok(obj),
+                Object::BreakValue => break,
+                _ => continue,
             }
         }
+
+        Ok(Object::UnitValue)
     }
 
     fn eval_expression(
@@ -112,37 +635,82 @@ impl<'a> Evaluator<'a> {
         expr: Expression,
         within_statement: bool,
     ) -> Result<Object, EvalError> {
+        // arms give back results, which are unwrapped once: propagating errors in every arm
+        // would give each of them its own temporaries, growing the stack used by each call
         let obj = match expr {
-            Expression::IntegerLiteral(lit) => Object::IntegerValue(lit),
-            Expression::BooleanLiteral(lit) => Object::BooleanValue(lit),
-            Expression::StringLiteral(lit) => Object::StringValue(lit),
-            Expression::Identifier(name) => self.env.borrow().get(&name)?,
-            Expression::ArrayLiteral(expressions) => self.eval_array_expression(expressions)?,
-            Expression::MapLiteral(map) => self.eval_map_expression(map)?,
+            Expression::IntegerLiteral(lit) => Ok(Object::IntegerValue(lit)),
+            Expression::BooleanLiteral(lit) => Ok(Object::BooleanValue(lit)),
+            Expression::NilLiteral => Ok(Object::NilValue),
+            Expression::StringLiteral(lit) => Ok(Object::StringValue(lit)),
+            // built-in functions can be used as values too (e.g. passed as arguments),
+            // unless a binding with the same name exists
+            Expression::Identifier(name) => self
+                .env
+                .borrow()
+                .get(&name)
+                .or_else(|err| BuiltinFunction::lookup_function(&name).map_err(|_| err)),
+            Expression::ArrayLiteral(expressions) => self.eval_array_expression(expressions),
+            Expression::TupleLiteral(expressions) if expressions.is_empty() => {
+                Ok(Object::UnitValue)
+            }
+            Expression::TupleLiteral(expressions) => self.eval_array_expression(expressions),
+            Expression::MapLiteral(map) => self.eval_map_expression(map),
             Expression::BinaryExpression {
                 left,
                 operator,
                 right,
-            } => self.eval_binary_expression(*left, operator, *right)?,
+            } => self.eval_binary_expression(*left, operator, *right),
             Expression::UnaryExpression { operator, value } => {
-                self.eval_unary_expression(operator, *value)?
+                self.eval_unary_expression(operator, *value)
             }
-            Expression::GroupedExpression(expr) => self.eval_expression(*expr, within_statement)?,
+            Expression::GroupedExpression(expr) => self.eval_expression(*expr, within_statement),
             Expression::CallExpression { path, arguments } => {
-                self.eval_call_expression(*path, arguments)?
+                self.eval_call_expression(*path, arguments)
             }
             Expression::IndexExpression { value, index } => {
-                self.eval_index_expression(*value, *index)?
+                self.eval_index_expression(*value, *index)
+            }
+            Expression::SliceExpression { value, start, end } => {
+                self.eval_slice_expression(*value, start, end)
             }
             Expression::IfExpression {
                 condition,
                 consequence,
                 alternative,
-            } => self.eval_if_expression(*condition, *consequence, alternative)?,
+            } => self.eval_if_expression(*condition, *consequence, alternative),
             Expression::FunctionExpression { parameters, body } => {
-                self.eval_function_expression(parameters, *body)?
+                self.eval_function_expression(parameters, body)
             }
-        };
+            Expression::ConditionalExpression {
+                consequence,
+                condition,
+                alternative,
+            } => self.eval_conditional_expression(
+                *consequence,
+                *condition,
+                *alternative,
+                "`a if cond else b`",
+                within_statement,
+            ),
+            Expression::TernaryExpression {
+                condition,
+                consequence,
+                alternative,
+            } => self.eval_conditional_expression(
+                *consequence,
+                *condition,
+                *alternative,
+                "`cond ? a : b`",
+                within_statement,
+            ),
+            // the body is a block, so it's evaluated in a child environment
+            Expression::DoExpression(body) => self.eval_statement(*body),
+            Expression::MatchExpression {
+                subject,
+                arms,
+                default,
+            } => self.eval_match_expression(*subject, arms, default, within_statement),
+        }?;
 
         // unwrap return values
         if let Object::ReturnValue(ref inner_obj) = obj {
@@ -154,42 +722,99 @@ impl<'a> Evaluator<'a> {
         Ok(obj)
     }
 
+    fn eval_conditional_expression(
+        &mut self,
+        consequence: Expression,
+        condition: Expression,
+        alternative: Expression,
+        construct: &str,
+        within_statement: bool,
+    ) -> Result<Object, EvalError> {
+        let condition = eval_operand!(self, condition);
+
+        if self.check_condition(condition, construct)? {
+            self.eval_expression(consequence, within_statement)
+        } else {
+            self.eval_expression(alternative, within_statement)
+        }
+    }
+
     fn eval_binary_expression(
         &mut self,
         left: Expression,
         operator: TokenKind,
         right: Expression,
     ) -> Result<Object, EvalError> {
-        let left_obj = self.eval_expression(left, false)?;
-        let right_obj = self.eval_expression(right, false)?;
+        let left_obj = eval_operand!(self, left);
+        let right_obj = eval_operand!(self, right);
+
+        if let Some(method) = operator_method(&left_obj, &right_obj, &operator) {
+            return self.call_function(method, vec![left_obj, right_obj]);
+        }
 
         let obj = match (left_obj, right_obj) {
-            (Object::IntegerValue(lhs), Object::IntegerValue(rhs)) => match operator {
-                TokenKind::Plus => Object::IntegerValue(lhs + rhs),
-                TokenKind::Minus => Object::IntegerValue(lhs - rhs),
-                TokenKind::Asterisk => Object::IntegerValue(lhs * rhs),
-                TokenKind::Equal => Object::BooleanValue(lhs == rhs),
-                TokenKind::NotEqual => Object::BooleanValue(lhs != rhs),
-                TokenKind::LessThan => Object::BooleanValue(lhs < rhs),
-                TokenKind::GreaterThan => Object::BooleanValue(lhs > rhs),
-                TokenKind::LessThanEqual => Object::BooleanValue(lhs <= rhs),
-                TokenKind::GreaterThanEqual => Object::BooleanValue(lhs >= rhs),
-                TokenKind::Percentage => {
-                    if rhs == 0 {
-                        return Err(EvalError::ModuloByZero);
-                    } else {
-                        Object::IntegerValue(lhs % rhs)
+            (Object::IntegerValue(lhs), Object::IntegerValue(rhs)) => {
+                let overflow = || EvalError::IntegerOverflow(format!("{lhs} {operator} {rhs}"));
+
+                match operator {
+                    TokenKind::Plus => {
+                        Object::IntegerValue(lhs.checked_add(rhs).ok_or_else(overflow)?)
                     }
-                }
-                TokenKind::Slash => {
-                    if rhs == 0 {
-                        return Err(EvalError::DivisionByZero);
-                    } else {
-                        Object::IntegerValue(lhs / rhs)
+                    TokenKind::Minus => {
+                        Object::IntegerValue(lhs.checked_sub(rhs).ok_or_else(overflow)?)
+                    }
+                    TokenKind::Asterisk => {
+                        Object::IntegerValue(lhs.checked_mul(rhs).ok_or_else(overflow)?)
+                    }
+                    TokenKind::Equal => Object::BooleanValue(lhs == rhs),
+                    TokenKind::NotEqual => Object::BooleanValue(lhs != rhs),
+                    TokenKind::LessThan => Object::BooleanValue(lhs < rhs),
+                    TokenKind::GreaterThan => Object::BooleanValue(lhs > rhs),
+                    TokenKind::LessThanEqual => Object::BooleanValue(lhs <= rhs),
+                    TokenKind::GreaterThanEqual => Object::BooleanValue(lhs >= rhs),
+                    TokenKind::Percentage => {
+                        if rhs == 0 {
+                            return Err(EvalError::ModuloByZero);
+                        } else {
+                            // `i32::MIN % -1` overflows as well
+                            Object::IntegerValue(lhs.checked_rem(rhs).ok_or_else(overflow)?)
+                        }
+                    }
+                    TokenKind::Slash => {
+                        if rhs == 0 {
+                            return Err(EvalError::DivisionByZero);
+                        } else {
+                            // `i32::MIN / -1` overflows as well
+                            Object::IntegerValue(lhs.checked_div(rhs).ok_or_else(overflow)?)
+                        }
+                    }
+                    TokenKind::Ampersand => Object::IntegerValue(lhs & rhs),
+                    TokenKind::Pipe => Object::IntegerValue(lhs | rhs),
+                    TokenKind::Caret => Object::IntegerValue(lhs ^ rhs),
+                    // shifts by a negative amount or by the whole width of `i32` are rejected
+                    TokenKind::ShiftLeft | TokenKind::ShiftRight => {
+                        let amount = u32::try_from(rhs)
+                            .ok()
+                            .filter(|&amount| amount < i32::BITS)
+                            .ok_or(EvalError::InvalidShift(rhs))?;
+
+                        if operator == TokenKind::ShiftRight {
+                            Object::IntegerValue(lhs >> amount)
+                        } else if (lhs << amount) >> amount == lhs {
+                            Object::IntegerValue(lhs << amount)
+                        } else {
+                            // bits shifted out (or into the sign) would change the value
+                            return Err(overflow());
+                        }
                     }
+                    TokenKind::Power => {
+                        let exponent =
+                            u32::try_from(rhs).map_err(|_| EvalError::NegativeExponent(rhs))?;
+                        Object::IntegerValue(lhs.checked_pow(exponent).ok_or_else(overflow)?)
+                    }
+                    _ => return Err(EvalError::UnsupportedOperator(operator)),
                 }
-                _ => return Err(EvalError::UnsupportedOperator(operator)),
-            },
+            }
 
             (Object::BooleanValue(lhs), Object::BooleanValue(rhs)) => match operator {
                 TokenKind::Equal => Object::BooleanValue(lhs == rhs),
@@ -200,11 +825,68 @@ impl<'a> Evaluator<'a> {
             },
 
             (Object::StringValue(lhs), Object::StringValue(rhs)) => match operator {
-                TokenKind::Plus => Object::StringValue(lhs + &rhs),
+                TokenKind::Plus => {
+                    self.check_collection_size(lhs.len().saturating_add(rhs.len()))?;
+                    Object::StringValue(lhs + &rhs)
+                }
+                // strings are ordered lexicographically
+                TokenKind::Equal => Object::BooleanValue(lhs == rhs),
+                TokenKind::NotEqual => Object::BooleanValue(lhs != rhs),
+                TokenKind::LessThan => Object::BooleanValue(lhs < rhs),
+                TokenKind::GreaterThan => Object::BooleanValue(lhs > rhs),
+                TokenKind::LessThanEqual => Object::BooleanValue(lhs <= rhs),
+                TokenKind::GreaterThanEqual => Object::BooleanValue(lhs >= rhs),
+                _ => return Err(EvalError::UnsupportedOperator(operator)),
+            },
+
+            // times are moved by a number of seconds, and their difference is a number of seconds
+            (Object::TimeValue(lhs), Object::IntegerValue(rhs)) => {
+                let overflow = || EvalError::IntegerOverflow(format!("{lhs} {operator} {rhs}"));
+
+                match operator {
+                    TokenKind::Plus => {
+                        Object::TimeValue(lhs.checked_add(rhs.into()).ok_or_else(overflow)?)
+                    }
+                    TokenKind::Minus => {
+                        Object::TimeValue(lhs.checked_sub(rhs.into()).ok_or_else(overflow)?)
+                    }
+                    _ => return Err(EvalError::UnsupportedOperator(operator)),
+                }
+            }
+
+            (Object::IntegerValue(lhs), Object::TimeValue(rhs)) if operator == TokenKind::Plus => {
+                let overflow = || EvalError::IntegerOverflow(format!("{lhs} {operator} {rhs}"));
+                Object::TimeValue(rhs.checked_add(lhs.into()).ok_or_else(overflow)?)
+            }
+
+            (Object::TimeValue(lhs), Object::TimeValue(rhs)) => match operator {
+                TokenKind::Minus => {
+                    let overflow = || EvalError::IntegerOverflow(format!("{lhs} {operator} {rhs}"));
+                    let difference = lhs.checked_sub(rhs).ok_or_else(overflow)?;
+                    Object::IntegerValue(i32::try_from(difference).map_err(|_| overflow())?)
+                }
+                TokenKind::Equal => Object::BooleanValue(lhs == rhs),
+                TokenKind::NotEqual => Object::BooleanValue(lhs != rhs),
+                TokenKind::LessThan => Object::BooleanValue(lhs < rhs),
+                TokenKind::GreaterThan => Object::BooleanValue(lhs > rhs),
+                TokenKind::LessThanEqual => Object::BooleanValue(lhs <= rhs),
+                TokenKind::GreaterThanEqual => Object::BooleanValue(lhs >= rhs),
                 _ => return Err(EvalError::UnsupportedOperator(operator)),
             },
 
+            // any other pair of values (e.g. arrays, maps and their nested values) is compared
+            // structurally: values of different types are never equal, so `[1] != 1` is `true`
+            (lhs, rhs) if matches!(operator, TokenKind::Equal | TokenKind::NotEqual) => {
+                Object::BooleanValue((lhs == rhs) == (operator == TokenKind::Equal))
+            }
+
             (lhs, rhs) => {
+                if self.strict && (lhs == Object::UnitValue || rhs == Object::UnitValue) {
+                    return Err(EvalError::MissingValue(format!(
+                        "the operation '{operator}'"
+                    )));
+                }
+
                 return Err(EvalError::TypeMismatch(format!(
                     "Cannot perform operation '{operator}' between '{lhs}' and '{rhs}'",
                 )));
@@ -219,15 +901,31 @@ impl<'a> Evaluator<'a> {
         operator: TokenKind,
         value: Expression,
     ) -> Result<Object, EvalError> {
+        let value = eval_operand!(self, value);
+
+        if self.strict && value == Object::UnitValue {
+            return Err(EvalError::MissingValue(format!(
+                "the operation '{operator}'"
+            )));
+        }
+
         let obj = match operator {
-            TokenKind::Bang => match self.eval_expression(value, false)? {
-                Object::IntegerValue(lit) => Object::IntegerValue(!lit),
+            TokenKind::Bang => match value {
                 Object::BooleanValue(lit) => Object::BooleanValue(!lit),
+                // `!` is a logical not only, the bitwise complement of integers is `~`
+                _ => return Err(EvalError::UnsupportedOperator(operator)),
+            },
+
+            TokenKind::Tilde => match value {
+                Object::IntegerValue(lit) => Object::IntegerValue(!lit),
                 _ => return Err(EvalError::UnsupportedOperator(operator)),
             },
 
-            TokenKind::Minus => match self.eval_expression(value, false)? {
-                Object::IntegerValue(lit) => Object::IntegerValue(-lit),
+            TokenKind::Minus => match value {
+                Object::IntegerValue(lit) => Object::IntegerValue(
+                    lit.checked_neg()
+                        .ok_or_else(|| EvalError::IntegerOverflow(format!("-({lit})")))?,
+                ),
                 _ => return Err(EvalError::UnsupportedOperator(operator)),
             },
 
@@ -241,7 +939,7 @@ impl<'a> Evaluator<'a> {
         let mut objects: Vec<Object> = vec![];
 
         for expr in expressions {
-            objects.push(self.eval_expression(expr, false)?);
+            objects.push(eval_operand!(self, expr));
         }
 
         Ok(Object::ArrayValue(objects))
@@ -249,12 +947,18 @@ impl<'a> Evaluator<'a> {
 
     fn eval_map_expression(
         &mut self,
-        expr_map: HashMap<String, Expression>,
+        expr_map: Vec<(Expression, Expression)>,
     ) -> Result<Object, EvalError> {
-        let mut map: HashMap<String, Object> = HashMap::new();
+        let mut map: BTreeMap<HashableObject, Object> = BTreeMap::new();
 
+        // like in any map, later values of a repeated key replace the previous ones
         for (key, expr) in expr_map {
-            map.insert(key, self.eval_expression(expr, false)?);
+            let key = HashableObject::try_from(eval_operand!(self, key)).map_err(|key| {
+                EvalError::TypeMismatch(format!(
+                    "`{key}` cannot be a map key, only integers, booleans and strings can"
+                ))
+            })?;
+            map.insert(key, eval_operand!(self, expr));
         }
 
         Ok(Object::MapValue(map))
@@ -265,35 +969,128 @@ impl<'a> Evaluator<'a> {
         value: Expression,
         index: Expression,
     ) -> Result<Object, EvalError> {
-        let value = self.eval_expression(value, false)?;
-        let index = self.eval_expression(index, false)?;
+        let value = eval_operand!(self, value);
+        let index = eval_operand!(self, index);
 
-        match value {
-            Object::ArrayValue(objects) => {
-                if let Object::IntegerValue(index) = index {
-                    let id = usize::try_from(index)
-                        .or_else(|err| return Err(ParserError::IntConversionError(err)))?;
+        if self.strict && (value == Object::UnitValue || index == Object::UnitValue) {
+            return Err(EvalError::MissingValue("an index expression".to_owned()));
+        }
 
-                    let item = objects
-                        .get(id)
-                        .ok_or(EvalError::IndexOutOfBounds(objects.len(), id))?;
+        index_object(value, index)
+    }
 
-                    Ok(item.clone())
-                } else {
-                    return Err(EvalError::InvalidIndexType);
+    fn eval_slice_expression(
+        &mut self,
+        value: Expression,
+        start: Option<Box<Expression>>,
+        end: Option<Box<Expression>>,
+    ) -> Result<Object, EvalError> {
+        let value = eval_operand!(self, value);
+
+        let mut bounds = [None, None];
+        for (bound, expr) in bounds.iter_mut().zip([start, end]) {
+            if let Some(expr) = expr {
+                match eval_operand!(self, *expr) {
+                    Object::IntegerValue(index) => *bound = Some(index),
+                    _ => return Err(EvalError::InvalidIndexType),
                 }
             }
-            Object::MapValue(map) => {
-                if let Object::StringValue(key) = index {
-                    let item = map.get(&key).ok_or(EvalError::ValueNotFound(key))?;
+        }
+        let [start, end] = bounds;
 
-                    Ok(item.clone())
-                } else {
+        let obj = match value {
+            Object::ArrayValue(objects) => {
+                let range = slice_range(start, end, objects.len());
+                Object::ArrayValue(objects[range].to_vec())
+            }
+            Object::BytesValue(bytes) => {
+                let range = slice_range(start, end, bytes.len());
+                Object::BytesValue(bytes[range].to_vec())
+            }
+            // strings are sliced by characters, not by bytes
+            Object::StringValue(text) => {
+                let range = slice_range(start, end, text.chars().count());
+                Object::StringValue(text.chars().skip(range.start).take(range.len()).collect())
+            }
+            _ => return Err(EvalError::InvalidIndexUsage),
+        };
+
+        Ok(obj)
+    }
+
+    fn eval_index_assign_statement(
+        &mut self,
+        target: Expression,
+        index: Expression,
+        value: Expression,
+    ) -> Result<Object, EvalError> {
+        // flatten `a[i][j]` into the variable name and the list of indices
+        let mut path = vec![index];
+        let mut target = target;
+        let name = loop {
+            match target {
+                Expression::Identifier(name) => break name,
+                Expression::IndexExpression { value, index } => {
+                    path.push(*index);
+                    target = *value;
+                }
+                _ => return Err(EvalError::InvalidIndexUsage),
+            }
+        };
+
+        // evaluate the indices from left to right, then the assigned value
+        let mut indices = Vec::with_capacity(path.len());
+        for index in path.into_iter().rev() {
+            indices.push(eval_operand!(self, index));
+        }
+        let value = eval_operand!(self, value);
+
+        let mut root = self.env.borrow().get(&name)?;
+        Self::set_index(&mut root, &indices, value)?;
+        self.env.borrow_mut().assign(name, root)?;
+
+        Ok(Object::UnitValue)
+    }
+
+    /// Walks `container` through `indices` and replaces the element found at the end.
+    /// Map keys are inserted when missing, array indices must already exist.
+    fn set_index(
+        container: &mut Object,
+        indices: &[Object],
+        value: Object,
+    ) -> Result<(), EvalError> {
+        let Some((index, rest)) = indices.split_first() else {
+            *container = value;
+            return Ok(());
+        };
+
+        let element = match container {
+            Object::ArrayValue(objects) => {
+                let Object::IntegerValue(index) = index else {
+                    return Err(EvalError::InvalidIndexType);
+                };
+
+                let id = resolve_index(*index, objects.len())?;
+
+                &mut objects[id]
+            }
+            Object::MapValue(map) => {
+                let Ok(key) = HashableObject::try_from(index.clone()) else {
                     return Err(EvalError::InvalidIndexType);
+                };
+
+                if rest.is_empty() {
+                    map.insert(key, value);
+                    return Ok(());
                 }
+
+                let not_found = EvalError::ValueNotFound(key.to_string());
+                map.get_mut(&key).ok_or(not_found)?
             }
             _ => return Err(EvalError::InvalidIndexUsage),
-        }
+        };
+
+        Self::set_index(element, rest, value)
     }
 
     fn eval_if_expression(
@@ -302,30 +1099,56 @@ impl<'a> Evaluator<'a> {
         consequence: Statement,
         alternative: Option<Box<Statement>>,
     ) -> Result<Object, EvalError> {
-        let obj = match self.eval_expression(condition, false)? {
-            Object::BooleanValue(lit) => {
-                if lit {
-                    self.eval_statement(consequence)?
-                } else if let Some(alt) = alternative {
-                    self.eval_statement(*alt)?
-                } else {
-                    Object::UnitValue
-                }
-            }
-            _ => {
-                return Err(EvalError::TypeMismatch(
-                    "`if` condition must be a boolean".to_owned(),
-                ))
-            }
+        let condition = eval_operand!(self, condition);
+
+        let obj = if self.check_condition(condition, "`if`")? {
+            self.eval_statement(consequence)?
+        } else if let Some(alt) = alternative {
+            self.eval_statement(*alt)?
+        } else {
+            Object::UnitValue
         };
 
         Ok(obj)
     }
 
+    fn eval_match_expression(
+        &mut self,
+        subject: Expression,
+        arms: Vec<(Expression, Expression)>,
+        default: Option<Box<Expression>>,
+        within_statement: bool,
+    ) -> Result<Object, EvalError> {
+        let subject = eval_operand!(self, subject);
+
+        for (pattern, value) in arms {
+            // patterns are literals, so they can't fail nor have side effects
+            if self.eval_expression(pattern, false)? == subject {
+                return self.eval_expression(value, within_statement);
+            }
+        }
+
+        match default {
+            Some(default) => self.eval_expression(*default, within_statement),
+            None => Ok(Object::UnitValue),
+        }
+    }
+
+    /// Checks the evaluated condition of a branching construct, named `construct` in errors.
+    fn check_condition(&self, condition: Object, construct: &str) -> Result<bool, EvalError> {
+        match condition {
+            Object::BooleanValue(lit) => Ok(lit),
+            value if self.truthiness => Ok(value.is_truthy()),
+            _ => Err(EvalError::TypeMismatch(format!(
+                "{construct} condition must be a boolean"
+            ))),
+        }
+    }
+
     fn eval_function_expression(
         &mut self,
         parameters: Vec<String>,
-        body: Statement,
+        body: Box<Statement>,
     ) -> Result<Object, EvalError> {
         let closure = Closure {
             parameters,
@@ -333,7 +1156,7 @@ impl<'a> Evaluator<'a> {
             env: self.create_enclosed_env(),
         };
 
-        Ok(Object::FunctionValue(closure))
+        Ok(Object::FunctionValue(FunctionRef(Rc::new(closure))))
     }
 
     fn eval_call_expression(
@@ -346,15 +1169,34 @@ impl<'a> Evaluator<'a> {
                 // built-in functions are searched through before user-defined ones
                 BuiltinFunction::lookup_function(&path).or_else(|_| self.env.borrow().get(&path))?
             }
-            expr => self.eval_expression(expr, false)?,
+            expr => eval_operand!(self, expr),
+        };
+
+        // evaluate arguments in the current scope
+        let arguments = match self.eval_call_expression_arguments(arguments)? {
+            ControlFlow::Continue(arguments) => arguments,
+            // e.g. `f(if done { break; } else { 1 })` leaves the loop without calling `f`
+            ControlFlow::Break(obj) => return Ok(obj),
         };
 
+        self.call_function(function, arguments)
+    }
+
+    /// Calls a function object with already evaluated arguments.
+    /// The result is never a *return value*: returns stop at the function boundary.
+    fn call_function(
+        &mut self,
+        function: Object,
+        arguments: Vec<Object>,
+    ) -> Result<Object, EvalError> {
         let obj = match function {
-            Object::FunctionValue(Closure {
-                parameters,
-                body,
-                env,
-            }) => {
+            Object::FunctionValue(FunctionRef(closure)) => {
+                let Closure {
+                    parameters,
+                    body,
+                    env,
+                } = closure.as_ref();
+
                 if parameters.len() != arguments.len() {
                     return Err(EvalError::FunctionCallWrongArity(
                         parameters.len() as u8,
@@ -362,108 +1204,86 @@ impl<'a> Evaluator<'a> {
                     ));
                 }
 
-                // evaluate arguments in the current scope
-                let arguments = self.eval_call_expression_arguments(arguments)?;
-
-                // switch to the closure environment
-                let outer_env = std::mem::replace(&mut self.env, env);
-
-                // add bindings in the closure environment
-                for (param, arg) in parameters.into_iter().zip(arguments.into_iter()) {
-                    self.env.borrow_mut().set(param, arg);
+                if self.depth >= self.max_depth {
+                    return Err(EvalError::RecursionLimitExceeded(self.max_depth));
                 }
 
-                // evaluate the closure body
-                let body_obj = self.eval_statement(body)?;
+                // every call gets its own scope inside the closure environment,
+                // so that recursive calls don't overwrite each other's parameters
+                let call_env = Rc::new(RefCell::new(Environment {
+                    outer: Some(env.clone()),
+                    ..Default::default()
+                }));
+                let outer_env = std::mem::replace(&mut self.env, call_env);
+
+                // add bindings in the closure environment, then evaluate the closure body
+                self.depth += 1;
+                // the body is cloned since evaluating consumes it
+                let body_obj = parameters
+                    .iter()
+                    .zip(arguments)
+                    .try_for_each(|(param, arg)| self.env.borrow_mut().set(param.clone(), arg))
+                    .and_then(|_| self.eval_statement(body.as_ref().clone()));
+
                 // go back to the old environment
+                self.depth -= 1;
                 self.env = outer_env;
 
-                body_obj
+                match body_obj? {
+                    Object::ReturnValue(obj) => *obj,
+                    // loop controls can't cross the function boundary
+                    Object::BreakValue => return Err(EvalError::BreakOutsideLoop),
+                    Object::ContinueValue => return Err(EvalError::ContinueOutsideLoop),
+                    obj => obj,
+                }
             }
 
-            Object::BuiltinValue(builtin) => match builtin {
-                BuiltinFunction::Len => {
-                    if arguments.len() != 1 {
-                        return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
-                    }
-
-                    let arguments = self.eval_call_expression_arguments(arguments)?;
-                    // unwrapping is fine, this element surely exist because of the previous check
-                    let arg = arguments.get(0).unwrap();
-
-                    let length: i32 = match arg {
-                        Object::StringValue(text) => text
-                            .len()
-                            .try_into()
-                            .or_else(|err| return Err(ParserError::IntConversionError(err)))?,
-
-                        Object::ArrayValue(objects) => objects
-                            .len()
-                            .try_into()
-                            .or_else(|err| return Err(ParserError::IntConversionError(err)))?,
-
-                        _ => {
-                            return Err(EvalError::UnsupportedArgumentType(format!(
-                                "`{}` only retrieves the length of strings and arrays",
-                                BuiltinFunction::Len
-                            )));
-                        }
-                    };
-
-                    Object::IntegerValue(length)
-                }
+            Object::BuiltinValue(builtin) => self.eval_builtin_function(builtin, arguments)?,
 
-                BuiltinFunction::Append => {
-                    if arguments.len() < 2 {
-                        return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
-                    }
+            Object::NativeValue(native) => Self::call_native(native, arguments)?,
 
-                    let mut arguments = self.eval_call_expression_arguments(arguments)?;
-                    let (first, rest) = arguments.split_first_mut().unwrap();
+            Object::RedefinableValue(cell) => {
+                let function = cell.function.borrow().clone();
+                self.call_function(function, arguments)?
+            }
 
-                    if let Object::ArrayValue(objects) = first {
-                        objects.extend_from_slice(rest);
-                        // return a new array, rather than modifying the existing one
-                        Object::ArrayValue(objects.clone())
-                    } else {
-                        return Err(EvalError::UnsupportedArgumentType(format!(
-                            "`{}` only works on arrays",
-                            BuiltinFunction::Append
-                        )));
-                    }
+            Object::MemoizedValue(Memoized { function, cache }) => {
+                // only calls whose arguments are all hashable can be cached
+                let key = arguments
+                    .iter()
+                    .cloned()
+                    .map(|arg| HashableObject::try_from(arg).ok())
+                    .collect::<Option<Vec<HashableObject>>>();
+
+                if let Some(obj) = key
+                    .as_ref()
+                    .and_then(|key| cache.borrow().get(key).cloned())
+                {
+                    return Ok(obj);
                 }
 
-                BuiltinFunction::Rest => {
-                    if arguments.len() != 1 {
-                        return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
-                    }
-
-                    let arguments = self.eval_call_expression_arguments(arguments)?;
-                    // unwrapping is fine, this element surely exist because of the previous check
-                    let arg = arguments.get(0).unwrap();
+                let obj = self.call_function(*function, arguments)?;
 
-                    if let Object::ArrayValue(objects) = arg {
-                        // return a new array, rather than modifying the existing one
-                        Object::ArrayValue(objects[1..].to_vec())
-                    } else {
-                        return Err(EvalError::UnsupportedArgumentType(format!(
-                            "`{}` only works on arrays",
-                            BuiltinFunction::Append
-                        )));
-                    }
+                if let Some(key) = key {
+                    cache.borrow_mut().insert(key, obj.clone());
                 }
 
-                BuiltinFunction::Println => {
-                    let arguments = self.eval_call_expression_arguments(arguments)?;
-                    arguments.iter().for_each(|arg| println!("{arg}"));
-                    Object::UnitValue
-                }
-                BuiltinFunction::Print => {
-                    let arguments = self.eval_call_expression_arguments(arguments)?;
-                    arguments.iter().for_each(|arg| print!("{arg}"));
-                    Object::UnitValue
-                }
-            },
+                obj
+            }
+
+            // collections are named by their type, since printing them could be huge
+            Object::MapValue(_) => {
+                return Err(EvalError::FunctionNotFound(
+                    "a map is not callable, use indexing (e.g. `map[\"key\"]`) to get its values"
+                        .to_owned(),
+                ));
+            }
+            Object::ArrayValue(_) => {
+                return Err(EvalError::FunctionNotFound(
+                    "an array is not callable, use indexing (e.g. `array[0]`) to get its elements"
+                        .to_owned(),
+                ));
+            }
 
             other => {
                 return Err(EvalError::FunctionNotFound(format!(
@@ -475,14 +1295,38 @@ impl<'a> Evaluator<'a> {
         Ok(obj)
     }
 
+    fn call_native(
+        NativeFunction { name, function }: NativeFunction,
+        arguments: Vec<Object>,
+    ) -> Result<Object, EvalError> {
+        panic::catch_unwind(AssertUnwindSafe(|| function(arguments))).map_err(|payload| {
+            // panics carry either a `&str` or a `String`, when they have a message
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown cause".to_owned());
+
+            EvalError::NativePanic(format!("`{name}`: {message}"))
+        })?
+    }
+
     fn eval_call_expression_arguments(
         &mut self,
         arguments: Vec<Expression>,
-    ) -> Result<Vec<Object>, EvalError> {
-        Ok(arguments
-            .into_iter()
-            .map(|arg| self.eval_expression(arg, false))
-            .collect::<Result<Vec<Object>, EvalError>>()?)
+    ) -> Result<ControlFlow<Object, Vec<Object>>, EvalError> {
+        let mut objects = Vec::with_capacity(arguments.len());
+
+        for arg in arguments {
+            let obj = self.eval_expression(arg, false)?;
+            if is_loop_control(&obj) {
+                return Ok(ControlFlow::Break(obj));
+            }
+
+            objects.push(obj);
+        }
+
+        Ok(ControlFlow::Continue(objects))
     }
 
     /// Creates a new environment linked to the outer environment
@@ -496,9 +1340,29 @@ impl<'a> Evaluator<'a> {
 }
 
 #[cfg(test)]
-mod tests {
+pub(super) mod tests {
     use super::*;
 
+    /// Writer whose content can still be read once it's been given to an evaluator.
+    #[derive(Clone, Default)]
+    pub(super) struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl SharedBuffer {
+        pub(super) fn contents(&self) -> String {
+            String::from_utf8(self.0.borrow().clone()).unwrap()
+        }
+    }
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
     #[test]
     fn eval_integer_literal() {
         let input = "5";
@@ -523,6 +1387,37 @@ mod tests {
         assert_eq!(result, &Object::StringValue("foo".to_owned()));
     }
 
+    #[test]
+    fn eval_non_ascii_source() {
+        let input = r#"let s = "é"; let abc = 5; abc"#;
+        let mut evaluator = Evaluator::new(input);
+        assert_eq!(
+            evaluator.eval_program().unwrap()[2],
+            Object::IntegerValue(5)
+        );
+
+        let input = r#"let s = "日本"; let abc = 5; abc"#;
+        let mut evaluator = Evaluator::new(input);
+        assert_eq!(
+            evaluator.eval_program().unwrap()[2],
+            Object::IntegerValue(5)
+        );
+
+        let input = r#"let s = "é";  x"#;
+        let mut evaluator = Evaluator::new(input);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::IdentifierNotFound(name)) if name == "x"
+        ));
+
+        let input = r#"let café = "日本"; café + "…""#;
+        let mut evaluator = Evaluator::new(input);
+        assert_eq!(
+            evaluator.eval_program().unwrap()[1],
+            Object::StringValue("日本…".to_owned())
+        );
+    }
+
     #[test]
     fn eval_string_concatenation() {
         let input = r#"
@@ -605,151 +1500,1662 @@ mod tests {
     }
 
     #[test]
-    fn eval_unary_expressions() {
+    fn eval_string_comparison() {
         let tests = vec![
-            ("-2", &Object::IntegerValue(-2)),
-            ("!true", &Object::BooleanValue(false)),
-            ("!false", &Object::BooleanValue(true)),
-            ("!5", &Object::IntegerValue(-6)),
-            ("!!5", &Object::IntegerValue(5)),
-            ("!0", &Object::IntegerValue(-1)),
-            ("!!true", &Object::BooleanValue(true)),
-            ("!!false", &Object::BooleanValue(false)),
+            (r#""apple" < "banana""#, true),
+            (r#""b" > "a""#, true),
+            (r#""x" == "x""#, true),
+            (r#""x" != "x""#, false),
+            (r#""ab" <= "abc""#, true),
+            (r#""B" >= "a""#, false),
+            ("\"\" < \"a\"", true),
         ];
 
         for (input, expected) in tests {
             let mut evaluator = Evaluator::new(input);
             let result = &evaluator.eval_program().unwrap()[0];
-            assert_eq!(result, expected);
+            assert_eq!(result, &Object::BooleanValue(expected), "{input}");
         }
+
+        let mut evaluator = Evaluator::new(r#""a" - "b""#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::UnsupportedOperator(TokenKind::Minus))
+        ));
     }
 
     #[test]
-    fn eval_if_expression() {
-        let tests = vec![
-            ("if true { 10 }", &Object::IntegerValue(10)),
-            ("if false { 10 }", &Object::UnitValue),
-            ("if 1 < 2 { 10 }", &Object::IntegerValue(10)),
-            ("if 1 > 2 { 10 }", &Object::UnitValue),
-            ("if 1 > 2 { 10 } else { 20 }", &Object::IntegerValue(20)),
-            ("if 1 < 2 { 10 } else { 20 }", &Object::IntegerValue(10)),
+    fn eval_operators_never_panic() {
+        // one or more values of every kind of object, edge cases included
+        let operands = [
+            "0",
+            "-1",
+            "2147483647",
+            "(-2147483647 - 1)",
+            "true",
+            r#""""#,
+            r#""\u{e9}t\u{e9}""#,
+            "[]",
+            "[1, [2]]",
+            "{}",
+            r#"{1: "a", "b": [true]}"#,
+            "bytes([1, 255])",
+            "time(0)",
+            "time(-2147483647 - 1)",
+            "fn(x) { x }",
+            "len",
+            "memoize(fn(x) { x })",
+            "native",
+            "nil",
+            "()",
+        ];
+        let binary_operators = [
+            "+", "-", "*", "/", "%", "**", "&", "|", "^", "<<", ">>", "==", "!=", "<", ">", "<=",
+            ">=", "&&", "||",
         ];
 
-        for (input, expected) in tests {
-            let mut evaluator = Evaluator::new(input);
-            let result = &evaluator.eval_program().unwrap()[0];
-            assert_eq!(result, expected);
+        let mut inputs = vec![];
+        for left in operands {
+            inputs.push(format!("!{left}"));
+            inputs.push(format!("-{left}"));
+            inputs.push(format!("~{left}"));
+
+            for right in operands {
+                for operator in binary_operators {
+                    inputs.push(format!("{left} {operator} {right}"));
+                }
+                inputs.push(format!("({left})[{right}]"));
+                inputs.push(format!("({left})[{right}:{right}]"));
+                inputs.push(format!("let a = {left}; a[{right}] = 1;"));
+            }
         }
-    }
 
-    #[test]
-    fn eval_array_expression() {
-        let input = r#"
-            let add = fn(x, y) { return x + y; };
-            [1 + 1, add(2, 2)];
-        "#;
-        let mut evaluator = Evaluator::new(input);
-        let result = &evaluator.eval_program().unwrap()[1];
-        assert_eq!(
-            result,
-            &Object::ArrayValue(vec![Object::IntegerValue(2), Object::IntegerValue(4)])
-        );
+        for input in &inputs {
+            for strict in [false, true] {
+                let mut evaluator = Evaluator::new(input);
+                evaluator.set_strict(strict);
+                evaluator
+                    .register_native("native", |_| Ok(Object::UnitValue))
+                    .unwrap();
+
+                // any result is fine, as long as it's a value or an error
+                let result = panic::catch_unwind(AssertUnwindSafe(|| evaluator.eval_program()));
+                let Ok(result) = result else {
+                    panic!("`{input}` panicked");
+                };
+                assert!(
+                    !matches!(result, Err(EvalError::ParsingError(_))),
+                    "`{input}` isn't a valid program"
+                );
+            }
+        }
     }
 
     #[test]
-    fn eval_map_expression() {
-        let input = r#"
-            let foo = { "temp": 1 + 1, "foo": 4 };
-            foo;
-        "#;
-        let mut evaluator = Evaluator::new(input);
-        let result = &evaluator.eval_program().unwrap()[1];
-        let mut expected = HashMap::new();
-        expected.insert("temp".to_owned(), Object::IntegerValue(2));
-        expected.insert("foo".to_owned(), Object::IntegerValue(4));
-        assert_eq!(result, &Object::MapValue(expected));
-    }
+    fn eval_strict_mode() {
+        let tests = vec![
+            "let f = fn() { if false { 1 } }; f() + 1;",
+            "let f = fn() { println() }; 1 * f();",
+            "let f = fn() { if false { 1 } }; -f();",
+            "let f = fn() { if false { 1 } }; f()[0];",
+            "let a = [1]; a[println()];",
+        ];
 
-    #[test]
-    fn eval_index_expression() {
-        let input = r#"
-            let a = [100, 200, 300, 400];
-            1 + a[1 + 1];
+        for input in tests {
+            let mut evaluator = Evaluator::new(input);
+            evaluator.set_strict(true);
+            let err = evaluator.eval_program().unwrap_err();
+            assert!(matches!(err, EvalError::MissingValue(_)), "{input}");
 
-            let b = { 
-                "foo": 2,
-                "bar": 4
-            };
-            b["foo"];
-        "#;
-        let mut evaluator = Evaluator::new(input);
-        let result = evaluator.eval_program().unwrap();
-        assert_eq!(&result[1], &Object::IntegerValue(301));
-        assert_eq!(&result[3], &Object::IntegerValue(2));
-    }
+            let mut evaluator = Evaluator::new(input);
+            let err = evaluator.eval_program().unwrap_err();
+            assert!(!matches!(err, EvalError::MissingValue(_)), "{input}");
+        }
 
-    #[test]
-    fn eval_function_expression() {
-        let input = r#"
-            let foo = fn(x) {
-                let double = fn(y) { y * 2; };
-                return double(x);
-            };
+        let mut evaluator = Evaluator::new("let f = fn() { if false { 1 } }; f() + 1;");
+        evaluator.set_strict(true);
+        let err = evaluator.eval_program().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cannot use `()` in the operation '+': did a function return no value?"
+        );
 
-            let bar = foo(3);
-            bar;
-        "#;
-        let mut evaluator = Evaluator::new(input);
-        let result = &evaluator.eval_program().unwrap()[2];
-        assert_eq!(result, &Object::IntegerValue(6));
+        // comparing with `()` is still allowed
+        let mut evaluator = Evaluator::new("let f = fn() { if false { 1 } }; f() == ();");
+        evaluator.set_strict(true);
+        let result = evaluator.eval_program().unwrap();
+        assert_eq!(result.last(), Some(&Object::BooleanValue(true)));
     }
 
     #[test]
-    fn eval_function_expressions() {
+    fn eval_truthiness() {
         let tests = vec![
-            ("let identity = fn(x) { x; }; identity(5);", 5),
-            ("let identity = fn(x) { return x; }; identity(5);", 5),
-            ("let double = fn(x) { x * 2; }; double(5);", 10),
-            ("let add = fn(x, y) { x + y; }; add(5, 5);", 10),
-            ("let add = fn(x, y) { x + y; }; add(5 + 5, add(5, 5));", 20),
-            ("1; fn(x) { x; }(5)", 5),
+            ("if len([1]) { 1 } else { 2 }", Object::IntegerValue(1)),
+            ("if 0 { 1 } else { 2 }", Object::IntegerValue(2)),
+            (r#"if "a" { 1 } else { 2 }"#, Object::IntegerValue(1)),
+            (r#"if "" { 1 } else { 2 }"#, Object::IntegerValue(2)),
+            ("if [] { 1 } else { 2 }", Object::IntegerValue(2)),
+            ("let m = {}; if m { 1 } else { 2 }", Object::IntegerValue(2)),
+            (
+                r#"let m = {"a": 1}; if m { 1 } else { 2 }"#,
+                Object::IntegerValue(1),
+            ),
+            ("if nil { 1 } else { 2 }", Object::IntegerValue(2)),
+            ("if () { 1 } else { 2 }", Object::IntegerValue(2)),
+            ("if len { 1 } else { 2 }", Object::IntegerValue(1)),
+            ("if false { 1 } else { 2 }", Object::IntegerValue(2)),
+            ("1 if -1 else 2", Object::IntegerValue(1)),
         ];
 
         for (input, expected) in tests {
             let mut evaluator = Evaluator::new(input);
-            let result = &evaluator.eval_program().unwrap()[1];
-            let expected_obj = &Object::IntegerValue(expected);
-            assert_eq!(result, expected_obj);
+            evaluator.set_truthiness(true);
+            let result = evaluator.eval_program().unwrap();
+            assert_eq!(result.last(), Some(&expected), "{input}");
+
+            // without truthiness only booleans are conditions
+            if !input.contains("false") {
+                let mut evaluator = Evaluator::new(input);
+                assert!(
+                    matches!(evaluator.eval_program(), Err(EvalError::TypeMismatch(_))),
+                    "{input}"
+                );
+            }
         }
+
+        // forks keep the setting
+        let mut evaluator = Evaluator::new("");
+        evaluator.set_truthiness(true);
+        let mut fork = evaluator.fork("if 1 { 1 }");
+        assert_eq!(fork.eval_program().unwrap(), vec![Object::IntegerValue(1)]);
     }
 
     #[test]
-    fn eval_block_statement() {
-        let input = r#"
-            let a = 2;
+    fn eval_redefine() {
+        let define = |input| Evaluator::new(input).eval_program().unwrap().remove(0);
 
-            {
-                let b = 3;
-                b;
-            }
+        let mut evaluator = Evaluator::new("let g = f; let h = fn() { f() * 10 }; g();");
+        evaluator.redefine("f", define("fn() { 1 }")).unwrap();
+        let result = evaluator.eval_program().unwrap();
+        assert_eq!(result.last(), Some(&Object::IntegerValue(1)));
 
-            a;
-        "#;
-        let mut evaluator = Evaluator::new(input);
-        let result = &evaluator.eval_program().unwrap()[2];
-        assert_eq!(result, &Object::IntegerValue(2));
+        // both the alias and the closure calling `f` pick up the new body
+        evaluator.redefine("f", define("fn() { 2 }")).unwrap();
+        let mut fork = evaluator.fork("[f(), g(), h(), g == f]");
+        assert_eq!(
+            fork.eval_program().unwrap(),
+            vec![Object::ArrayValue(vec![
+                Object::IntegerValue(2),
+                Object::IntegerValue(2),
+                Object::IntegerValue(20),
+                Object::BooleanValue(true),
+            ])]
+        );
+
+        // redefining with the cell itself keeps the current definition
+        let f = evaluator.fork("f").eval_program().unwrap().remove(0);
+        evaluator.redefine("f", f).unwrap();
+        let mut fork = evaluator.fork("g()");
+        assert_eq!(fork.eval_program().unwrap(), vec![Object::IntegerValue(2)]);
+
+        assert!(matches!(
+            evaluator.redefine("f", Object::IntegerValue(1)),
+            Err(EvalError::TypeMismatch(_))
+        ));
     }
 
     #[test]
-    fn eval_assign_statement() {
-        let input = r#"
-            let a = 2;
-            a = a + 2;
-            a;
-        "#;
+    fn eval_successive_sources() {
+        let mut evaluator = Evaluator::new("");
+        assert_eq!(
+            evaluator.eval_str("let x = 1;").unwrap(),
+            vec![Object::UnitValue]
+        );
+        assert_eq!(
+            evaluator.eval_str("x + 1").unwrap(),
+            vec![Object::IntegerValue(2)]
+        );
+
+        // sources are owned by the caller and can be dropped right after being evaluated
+        for line in ["fn inc(n) { n + 1 }", "x = inc(x);"] {
+            let line = String::from(line);
+            evaluator.eval_str(&line).unwrap();
+        }
+        assert_eq!(
+            evaluator.eval_str("x").unwrap(),
+            vec![Object::IntegerValue(2)]
+        );
+
+        // errors leave the bindings made so far untouched
+        assert!(matches!(
+            evaluator.eval_str("let y = 1; missing;"),
+            Err(EvalError::IdentifierNotFound(_))
+        ));
+        assert!(matches!(
+            evaluator.eval_str("let x = ;"),
+            Err(EvalError::ParsingError(_))
+        ));
+        assert_eq!(
+            evaluator.eval_str("x + y").unwrap(),
+            vec![Object::IntegerValue(3)]
+        );
+    }
+
+    #[test]
+    fn eval_deadline() {
+        let input = r#"
+            let r = range(1000);
+            for a in r { for b in r { for c in r { let x = a + b + c; } } }
+        "#;
+
+        let mut evaluator = Evaluator::new(input);
+        let start = Instant::now();
+        evaluator.set_deadline(start + std::time::Duration::from_millis(50));
+        assert!(matches!(evaluator.eval_program(), Err(EvalError::Timeout)));
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+
+        // function calls count as well, and forks keep the deadline
+        let mut evaluator = Evaluator::new("");
+        evaluator.set_deadline(Instant::now());
+        let mut fork = evaluator.fork("fn f(n) { n } for i in range(5000) { f(i); }");
+        assert!(matches!(fork.eval_program(), Err(EvalError::Timeout)));
+
+        // programs finishing in time aren't affected
+        let mut evaluator = Evaluator::new("let x = 0; for i in range(10) { x += i; } x;");
+        evaluator.set_deadline(Instant::now() + std::time::Duration::from_secs(60));
+        let result = evaluator.eval_program().unwrap();
+        assert_eq!(result.last(), Some(&Object::IntegerValue(45)));
+    }
+
+    #[test]
+    fn eval_native_function() {
+        let mut evaluator = Evaluator::new(
+            r#"
+            double(21);
+            map([1, 2], double);
+            fail(1);
+            "#,
+        );
+        evaluator
+            .register_native("double", |arguments| match arguments[..] {
+                [Object::IntegerValue(value)] => Ok(Object::IntegerValue(value * 2)),
+                _ => Err(EvalError::UnsupportedArgumentType("`double`".to_owned())),
+            })
+            .unwrap();
+        evaluator
+            .register_native("fail", |arguments| {
+                panic!("cannot handle {} arguments", arguments.len())
+            })
+            .unwrap();
+
+        let err = evaluator.eval_program().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Native function panicked: `fail`: cannot handle 1 arguments"
+        );
+
+        // the evaluator is still usable after the panic
+        let mut fork = evaluator.fork("double(21);\nmap([1, 2], double);");
+        let result = fork.eval_program().unwrap();
+        assert_eq!(&result[0], &Object::IntegerValue(42));
+        assert_eq!(
+            &result[1],
+            &Object::ArrayValue(vec![Object::IntegerValue(2), Object::IntegerValue(4)])
+        );
+    }
+
+    #[test]
+    fn eval_error_formatter() {
+        let mut evaluator = Evaluator::new("let a = 1;\nb;");
+        let err = evaluator.eval_program().unwrap_err();
+        assert_eq!(
+            evaluator.render_error(&err),
+            "| Qalo Error |\nIdentifier not found: b"
+        );
+
+        evaluator.set_error_formatter(Box::new(|err, input| {
+            format!(
+                r#"{{"error": "{err}", "lines": {}}}"#,
+                input.lines().count()
+            )
+        }));
+        assert_eq!(
+            evaluator.render_error(&err),
+            r#"{"error": "Identifier not found: b", "lines": 2}"#
+        );
+
+        // forks render errors the same way
+        let mut fork = evaluator.fork("c;");
+        let err = fork.eval_program().unwrap_err();
+        assert_eq!(
+            fork.render_error(&err),
+            r#"{"error": "Identifier not found: c", "lines": 1}"#
+        );
+
+        // errors are rendered against the source that raised them
+        let mut evaluator = Evaluator::new("let a = 1;\nb;");
+        evaluator.set_error_formatter(Box::new(|err, input| {
+            format!("{err} in {} lines", input.lines().count())
+        }));
+        let err = evaluator
+            .eval_str("let d = 1;\nlet e = 2;\nf;")
+            .unwrap_err();
+        assert_eq!(
+            evaluator.render_error(&err),
+            "Identifier not found: f in 3 lines"
+        );
+
+        let err = evaluator.eval_program().unwrap_err();
+        assert_eq!(
+            evaluator.render_error(&err),
+            "Identifier not found: b in 2 lines"
+        );
+    }
+
+    #[test]
+    fn eval_string_escapes() {
+        let mut evaluator = Evaluator::new(r#""\x41\x42" == "AB""#);
+        let result = &evaluator.eval_program().unwrap()[0];
+        assert_eq!(result, &Object::BooleanValue(true));
+
+        let mut evaluator = Evaluator::new(r#"let a = "\xZZ";"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::ParsingError(_))
+        ));
+    }
+
+    #[test]
+    fn eval_deep_equality() {
+        let tests = vec![
+            ("[1, [2]] == [1, [2]]", true),
+            ("[1, [2]] == [1, [3]]", false),
+            ("[1, 2] != [1, 2, 3]", true),
+            (r#"{"a": 1} != {"a": 2}"#, true),
+            (r#"{"a": [1, {"b": 2}]} == {"a": [1, {"b": 2}]}"#, true),
+            ("[1] == 1", false),
+            ("[1] != 1", true),
+            (r#"1 == "1""#, false),
+            ("() == ()", true),
+            ("let f = fn(x) { f(x) }; f == f", true),
+            ("fn(x) { x } == fn(x) { x }", false),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = evaluator.eval_program().unwrap().pop().unwrap();
+            assert_eq!(result, Object::BooleanValue(expected), "{input}");
+        }
+
+        let mut evaluator = Evaluator::new("[1] < [2]");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn eval_function_identity() {
+        let tests = vec![
+            ("fn f(x) { x } f == f", true),
+            ("fn f(x) { x } let g = f; g == f", true),
+            ("fn f(x) { x } [f, 1] == [f, 1]", true),
+            (r#"fn f(x) { x } {"f": f}["f"] == f"#, true),
+            ("fn f(x) { x } fn g(x) { x } f == g", false),
+            ("fn f(x) { x } fn g(x) { x } f != g", true),
+            ("let make = fn() { fn(x) { x } }; make() == make()", false),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = evaluator.eval_program().unwrap().pop().unwrap();
+            assert_eq!(result, Object::BooleanValue(expected), "{input}");
+        }
+    }
+
+    #[test]
+    fn eval_integer_overflow() {
+        let tests = vec![
+            "2147483647 + 1",
+            "-2147483647 - 2",
+            "65536 * 65536",
+            "(-2147483647 - 1) / -1",
+            "(-2147483647 - 1) % -1",
+            "-(-2147483647 - 1)",
+            "2 ** 31",
+        ];
+
+        for input in tests {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(evaluator.eval_program(), Err(EvalError::IntegerOverflow(_))),
+                "{input} should overflow"
+            );
+        }
+    }
+
+    #[test]
+    fn eval_operator_methods() {
+        let input = r#"
+            let vector = fn(x, y) {
+                return {
+                    "x": x,
+                    "y": y,
+                    "__add__": fn(a, b) { vector(a["x"] + b["x"], a["y"] + b["y"]) },
+                    "__mul__": fn(a, b) { a["x"] * b["x"] + a["y"] * b["y"] },
+                };
+            };
+            let sum = vector(1, 2) + vector(3, 4);
+            [sum["x"], sum["y"]];
+            vector(1, 2) * vector(3, 4);
+            vector(1, 2) + vector(1, 2) + vector(1, 2) == vector(3, 6);
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = evaluator.eval_program().unwrap();
+        assert_eq!(
+            &result[2],
+            &Object::ArrayValue(vec![Object::IntegerValue(4), Object::IntegerValue(6)])
+        );
+        assert_eq!(&result[3], &Object::IntegerValue(11));
+        // equality stays structural, and the methods are part of the structure
+        assert_eq!(&result[4], &Object::BooleanValue(false));
+
+        // operators without a method, or with a non-map operand, aren't overridden
+        for input in [
+            r#"{"__add__": fn(a, b) { 1 }} - {}"#,
+            r#"{"__add__": fn(a, b) { 1 }} + 1"#,
+            r#"{"__add__": 1} + {}"#,
+        ] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(evaluator.eval_program(), Err(EvalError::TypeMismatch(_))),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn eval_bitwise_expression() {
+        let tests = vec![
+            ("6 & 3", 2),
+            ("5 | 2", 7),
+            ("5 ^ 1", 4),
+            ("~0", -1),
+            ("~5", -6),
+            ("-1 & 255", 255),
+            ("1 | 2 ^ 3 & 6", 1),
+            ("~0 ^ 1", -2),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, &Object::IntegerValue(expected), "{input}");
+        }
+
+        for input in ["true & false", "~true", r#""a" | "b""#] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(evaluator.eval_program().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn eval_shift_expression() {
+        let tests = vec![
+            ("1 << 4", 16),
+            ("256 >> 2", 64),
+            ("1 << 0", 1),
+            ("-1 << 31", -2147483648),
+            ("1 << 30", 1073741824),
+            ("-16 >> 2", -4),
+            ("-1 >> 31", -1),
+            ("1 << 2 + 1", 8),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, &Object::IntegerValue(expected), "{input}");
+        }
+
+        for input in ["1 << 31", "3 << 30", "-3 << 30", "2147483647 << 1"] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(evaluator.eval_program(), Err(EvalError::IntegerOverflow(_))),
+                "{input}"
+            );
+        }
+
+        for (input, amount) in [("1 << 32", 32), ("256 >> 40", 40), ("1 << -1", -1)] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(evaluator.eval_program(), Err(EvalError::InvalidShift(n)) if n == amount),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn eval_power_expression() {
+        let tests = vec![
+            ("2 ** 10", 1024),
+            ("2 ** 0", 1),
+            ("0 ** 0", 1),
+            ("(-2) ** 3", -8),
+            ("-2 ** 2", -4),
+            ("2 ** 3 ** 2", 512),
+            ("2 * 3 ** 2", 18),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, &Object::IntegerValue(expected), "{input}");
+        }
+
+        let mut evaluator = Evaluator::new("2 ** -1");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::NegativeExponent(-1))
+        ));
+    }
+
+    #[test]
+    fn eval_unary_expressions() {
+        let tests = vec![
+            ("-2", &Object::IntegerValue(-2)),
+            ("!true", &Object::BooleanValue(false)),
+            ("!false", &Object::BooleanValue(true)),
+            ("~5", &Object::IntegerValue(-6)),
+            ("~~5", &Object::IntegerValue(5)),
+            ("~0", &Object::IntegerValue(-1)),
+            ("!!true", &Object::BooleanValue(true)),
+            ("!!false", &Object::BooleanValue(false)),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, expected);
+        }
+
+        for input in ["!5", "!!5", "!0", "!\"a\""] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(
+                    evaluator.eval_program(),
+                    Err(EvalError::UnsupportedOperator(TokenKind::Bang))
+                ),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn eval_if_expression() {
+        let tests = vec![
+            ("if true { 10 }", &Object::IntegerValue(10)),
+            ("if false { 10 }", &Object::UnitValue),
+            ("if 1 < 2 { 10 }", &Object::IntegerValue(10)),
+            ("if 1 > 2 { 10 }", &Object::UnitValue),
+            ("if 1 > 2 { 10 } else { 20 }", &Object::IntegerValue(20)),
+            ("if 1 < 2 { 10 } else { 20 }", &Object::IntegerValue(10)),
+            (
+                "if 1 > 2 { 10 } else if 2 > 1 { 20 } else { 30 }",
+                &Object::IntegerValue(20),
+            ),
+            (
+                "if 1 > 2 { 10 } else if 2 < 1 { 20 } else { 30 }",
+                &Object::IntegerValue(30),
+            ),
+            ("if 1 > 2 { 10 } else if 2 < 1 { 20 }", &Object::UnitValue),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, expected);
+        }
+    }
+
+    #[test]
+    fn eval_array_expression() {
+        let input = r#"
+            let add = fn(x, y) { return x + y; };
+            [1 + 1, add(2, 2)];
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap()[1];
+        assert_eq!(
+            result,
+            &Object::ArrayValue(vec![Object::IntegerValue(2), Object::IntegerValue(4)])
+        );
+    }
+
+    #[test]
+    fn eval_tuple_expression() {
+        let input = r#"
+            (1, 2);
+            (1,);
+            ();
+            (1);
+            let [a, b] = ("a", 1 + 1);
+            b;
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(
+            &result[0],
+            &Object::ArrayValue(vec![Object::IntegerValue(1), Object::IntegerValue(2)])
+        );
+        assert_eq!(
+            &result[1],
+            &Object::ArrayValue(vec![Object::IntegerValue(1)])
+        );
+        assert_eq!(&result[2], &Object::UnitValue);
+        assert_eq!(&result[3], &Object::IntegerValue(1));
+        assert_eq!(&result[5], &Object::IntegerValue(2));
+    }
+
+    #[test]
+    fn eval_map_expression() {
+        let input = r#"
+            let foo = { "temp": 1 + 1, "foo": 4 };
+            foo;
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap()[1];
+        let mut expected = BTreeMap::new();
+        expected.insert(
+            HashableObject::String("temp".to_owned()),
+            Object::IntegerValue(2),
+        );
+        expected.insert(
+            HashableObject::String("foo".to_owned()),
+            Object::IntegerValue(4),
+        );
+        assert_eq!(result, &Object::MapValue(expected));
+
+        // keys are evaluated like values
+        let input = r#"
+            let key = "k";
+            let map = {key: 1, 1 + 1: "two", -1: nil};
+            map;
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap()[2];
+        let expected = BTreeMap::from([
+            (
+                HashableObject::String("k".to_owned()),
+                Object::IntegerValue(1),
+            ),
+            (
+                HashableObject::Integer(2),
+                Object::StringValue("two".to_owned()),
+            ),
+            (HashableObject::Integer(-1), Object::NilValue),
+        ]);
+        assert_eq!(result, &Object::MapValue(expected));
+
+        let mut evaluator = Evaluator::new("let key = [1]; let map = {key: 1};");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn eval_nil_literal() {
+        let tests = vec![
+            ("nil", Object::NilValue),
+            ("nil == nil", Object::BooleanValue(true)),
+            ("nil != nil", Object::BooleanValue(false)),
+            ("nil == 0", Object::BooleanValue(false)),
+            ("nil == false", Object::BooleanValue(false)),
+            (r#"nil == """#, Object::BooleanValue(false)),
+            ("let f = fn() {}; f() == nil", Object::BooleanValue(false)),
+            ("type(nil)", Object::StringValue("nil".to_owned())),
+            ("str([nil])", Object::StringValue("[nil]".to_owned())),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = evaluator.eval_program().unwrap();
+            assert_eq!(result.last().unwrap(), &expected, "{input}");
+        }
+
+        let mut evaluator = Evaluator::new("nil + 1");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn eval_map_display() {
+        let input = r#"{"b": 1, "a": {"y": 2, "x": 3}, true: 4, 10: 5, 2: 6}"#;
+        let expected = r#"{2: 6, 10: 5, true: 4, "a": {"x": 3, "y": 2}, "b": 1}"#;
+
+        // the order doesn't depend on hashing, so it's the same for every evaluation
+        for _ in 0..10 {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn eval_map_non_string_keys() {
+        let input = r#"
+            let m = {1: "a", true: "b", "1": "c", -2: "d"};
+            m[1];
+            m[true];
+            m["1"];
+            m[-2];
+            m[1 + 1] = "e";
+            m[2];
+            let key = false;
+            let n = {key: 1, 3 * 2: 2};
+            n[false];
+            let seen = [];
+            for k in m { seen = append(seen, k); };
+            seen;
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(&result[1], &Object::StringValue("a".to_owned()));
+        assert_eq!(&result[2], &Object::StringValue("b".to_owned()));
+        assert_eq!(&result[3], &Object::StringValue("c".to_owned()));
+        assert_eq!(&result[4], &Object::StringValue("d".to_owned()));
+        assert_eq!(&result[6], &Object::StringValue("e".to_owned()));
+        assert_eq!(&result[9], &Object::IntegerValue(1));
+        // keys are sorted as integers, booleans and then strings
+        assert_eq!(
+            &result[12],
+            &Object::ArrayValue(vec![
+                Object::IntegerValue(-2),
+                Object::IntegerValue(1),
+                Object::IntegerValue(2),
+                Object::BooleanValue(true),
+                Object::StringValue("1".to_owned()),
+            ])
+        );
+
+        let mut evaluator = Evaluator::new("{true: 1}[false]");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::ValueNotFound(key)) if key == "false"
+        ));
+
+        let mut evaluator = Evaluator::new("let m = {[1]: 1};");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::TypeMismatch(_))
+        ));
+
+        let mut evaluator = Evaluator::new("{1: 1}[[1]]");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::InvalidIndexType)
+        ));
+    }
+
+    #[test]
+    fn eval_slice_expression() {
+        let integers = |values: &[i32]| {
+            Object::ArrayValue(values.iter().map(|v| Object::IntegerValue(*v)).collect())
+        };
+        let string = |value: &str| Object::StringValue(value.to_owned());
+
+        let tests = vec![
+            ("[1, 2, 3, 4][1:3]", integers(&[2, 3])),
+            ("[1, 2, 3, 4][:2]", integers(&[1, 2])),
+            ("[1, 2, 3, 4][2:]", integers(&[3, 4])),
+            ("[1, 2, 3, 4][:]", integers(&[1, 2, 3, 4])),
+            ("[1, 2, 3, 4][-3:-1]", integers(&[2, 3])),
+            ("[1, 2, 3, 4][3:1]", integers(&[])),
+            ("[1, 2, 3, 4][2:10]", integers(&[3, 4])),
+            ("[1, 2, 3, 4][-10:1]", integers(&[1])),
+            (r#""hello"[:2]"#, string("he")),
+            (r#""hello"[-3:]"#, string("llo")),
+            (r#""h\u{e9}llo"[1:3]"#, string("\u{e9}l")),
+            ("bytes([1, 2, 3])[1:]", Object::BytesValue(vec![2, 3])),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, &expected, "{input}");
+        }
+
+        let mut evaluator = Evaluator::new(r#"[1, 2]["a":]"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::InvalidIndexType)
+        ));
+
+        let mut evaluator = Evaluator::new(r#"{"a": 1}[0:1]"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::InvalidIndexUsage)
+        ));
+    }
+
+    #[test]
+    fn eval_negative_index_expression() {
+        let input = r#"
+            let a = [1, 2, 3];
+            a[-1];
+            a[-3];
+            "hello"[-2];
+            bytes([7, 8])[-1];
+            a[-1] = 30;
+            a;
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = evaluator.eval_program().unwrap();
+        assert_eq!(&result[1], &Object::IntegerValue(3));
+        assert_eq!(&result[2], &Object::IntegerValue(1));
+        assert_eq!(&result[3], &Object::StringValue("l".to_owned()));
+        assert_eq!(&result[4], &Object::IntegerValue(8));
+        assert_eq!(
+            &result[6],
+            &Object::ArrayValue(vec![
+                Object::IntegerValue(1),
+                Object::IntegerValue(2),
+                Object::IntegerValue(30)
+            ])
+        );
+
+        for input in ["[1, 2, 3][-4]", r#""abc"[-4]"#, "[][-1]"] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(
+                    evaluator.eval_program(),
+                    Err(EvalError::IndexOutOfBounds(_, _))
+                ),
+                "{input}"
+            );
+        }
+
+        let mut evaluator = Evaluator::new("[1, 2, 3][-4]");
+        assert_eq!(
+            evaluator.eval_program().unwrap_err().to_string(),
+            "This structure has 3 elements but the index -4 is out of bounds."
+        );
+    }
+
+    #[test]
+    fn eval_string_index_expression() {
+        let input = r#"
+            "hello"[1];
+            let s = "h\u{e9}llo";
+            s[1] + s[2];
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = evaluator.eval_program().unwrap();
+        assert_eq!(&result[0], &Object::StringValue("e".to_owned()));
+        assert_eq!(&result[2], &Object::StringValue("\u{e9}l".to_owned()));
+
+        let mut evaluator = Evaluator::new(r#""h\u{e9}llo"[5]"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::IndexOutOfBounds(5, 5))
+        ));
+
+        let mut evaluator = Evaluator::new(r#""hello"["h"]"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::InvalidIndexType)
+        ));
+    }
+
+    #[test]
+    fn eval_index_expression() {
+        let input = r#"
+            let a = [100, 200, 300, 400];
+            1 + a[1 + 1];
+
+            let b = { 
+                "foo": 2,
+                "bar": 4
+            };
+            b["foo"];
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = evaluator.eval_program().unwrap();
+        assert_eq!(&result[1], &Object::IntegerValue(301));
+        assert_eq!(&result[3], &Object::IntegerValue(2));
+    }
+
+    #[test]
+    fn eval_function_expression() {
+        let input = r#"
+            let foo = fn(x) {
+                let double = fn(y) { y * 2; };
+                return double(x);
+            };
+
+            let bar = foo(3);
+            bar;
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap()[2];
+        assert_eq!(result, &Object::IntegerValue(6));
+    }
+
+    #[test]
+    fn eval_function_expressions() {
+        let tests = vec![
+            ("let identity = fn(x) { x; }; identity(5);", 5),
+            ("let identity = fn(x) { return x; }; identity(5);", 5),
+            ("let double = fn(x) { x * 2; }; double(5);", 10),
+            ("let add = fn(x, y) { x + y; }; add(5, 5);", 10),
+            ("let add = fn(x, y) { x + y; }; add(5 + 5, add(5, 5));", 20),
+            ("1; fn(x) { x; }(5)", 5),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[1];
+            let expected_obj = &Object::IntegerValue(expected);
+            assert_eq!(result, expected_obj);
+        }
+    }
+
+    #[test]
+    fn eval_block_statement() {
+        let input = r#"
+            let a = 2;
+
+            {
+                let b = 3;
+                b;
+            }
+
+            a;
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap()[2];
+        assert_eq!(result, &Object::IntegerValue(2));
+    }
+
+    #[test]
+    fn eval_conditional_expression() {
+        let tests = vec![
+            ("let x = 1 if true else 2; x;", Object::IntegerValue(1)),
+            ("let x = 1 if false else 2; x;", Object::IntegerValue(2)),
+            ("1 + 1 if 1 > 2 else 3 * 3", Object::IntegerValue(9)),
+            ("1 if false else 2 if false else 3", Object::IntegerValue(3)),
+            (
+                "let f = fn(n) { \"neg\" if n < 0 else \"pos\" }; f(-1);",
+                Object::StringValue("neg".to_owned()),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = evaluator.eval_program().unwrap();
+            assert_eq!(result.last(), Some(&expected));
+        }
+
+        // only the chosen branch is evaluated
+        let mut evaluator = Evaluator::new("1 if true else missing");
+        assert!(evaluator.eval_program().is_ok());
+
+        let mut evaluator = Evaluator::new("1 if 0 else 2");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn eval_ternary_expression() {
+        let tests = vec![
+            ("true ? 1 : 2", Object::IntegerValue(1)),
+            ("false ? 1 : 2", Object::IntegerValue(2)),
+            ("1 > 2 ? 1 : 2 > 1 ? 2 : 3", Object::IntegerValue(2)),
+            ("true ? false ? 1 : 2 : 3", Object::IntegerValue(2)),
+            ("max(true ? 5 : 0, 3)", Object::IntegerValue(5)),
+            (
+                "let sign = fn(n) { n < 0 ? -1 : n == 0 ? 0 : 1 }; [sign(-5), sign(0), sign(5)]",
+                Object::ArrayValue(vec![
+                    Object::IntegerValue(-1),
+                    Object::IntegerValue(0),
+                    Object::IntegerValue(1),
+                ]),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = evaluator.eval_program().unwrap();
+            assert_eq!(result.last(), Some(&expected), "{input}");
+        }
+
+        // only the chosen branch is evaluated
+        let mut evaluator = Evaluator::new("true ? 1 : missing");
+        assert!(evaluator.eval_program().is_ok());
+
+        let mut evaluator = Evaluator::new("1 ? 1 : 2");
+        let err = evaluator.eval_program().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Type mismatch: `cond ? a : b` condition must be a boolean"
+        );
+
+        let mut evaluator = Evaluator::new("1 ? 1 : 2");
+        evaluator.set_truthiness(true);
+        assert_eq!(
+            evaluator.eval_program().unwrap(),
+            vec![Object::IntegerValue(1)]
+        );
+    }
+
+    #[test]
+    fn eval_method_calls() {
+        let input = r#"
+            fn double(x) { x * 2 }
+            let even = fn(x) { x % 4 == 0 };
+            [1, 2, 3, 4].map(double).filter(even).len();
+            "a,b".split(",").join("-");
+        "#;
+
+        let mut evaluator = Evaluator::new(input);
+        let result = evaluator.eval_program().unwrap();
+        assert_eq!(
+            result[result.len() - 2..],
+            [
+                Object::IntegerValue(2),
+                Object::StringValue("a-b".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn eval_pipe_forward() {
+        let input = r#"
+            fn double(x) { x * 2 }
+            5 |> double;
+            [1, 2, 3] |> map(double) |> len;
+            [3, 1, 2] |> filter(fn(x) { x > 1 }) |> map(double);
+        "#;
+
+        let mut evaluator = Evaluator::new(input);
+        let result = evaluator.eval_program().unwrap();
+        assert_eq!(
+            result[1..],
+            [
+                Object::IntegerValue(10),
+                Object::IntegerValue(3),
+                Object::ArrayValue(vec![Object::IntegerValue(6), Object::IntegerValue(4)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn eval_function_declaration() {
+        let input = r#"
+            fn factorial(n) {
+                if n <= 1 { 1 } else { n * factorial(n - 1) }
+            }
+
+            fn is_even(n) { n == 0 ? true : is_odd(n - 1) }
+            fn is_odd(n) { n == 0 ? false : is_even(n - 1) }
+
+            [factorial(5), is_even(10), is_odd(7)];
+        "#;
+
+        let mut evaluator = Evaluator::new(input);
+        let result = evaluator.eval_program().unwrap();
+        assert_eq!(
+            result.last(),
+            Some(&Object::ArrayValue(vec![
+                Object::IntegerValue(120),
+                Object::BooleanValue(true),
+                Object::BooleanValue(true),
+            ]))
+        );
+    }
+
+    #[test]
+    fn eval_match_expression() {
+        let input = r#"
+            fn describe(n) {
+                match n {
+                    0 => "zero",
+                    1 => "one",
+                    -1 => "minus one",
+                    _ => "many",
+                }
+            }
+
+            [describe(0), describe(1), describe(-1), describe(5), describe("1")];
+            match "b" { "a" => 1, "b" => 2 };
+            match true { false => 0 };
+            let f = fn(x) { match x { 1 => do { return 10; }, _ => 0 }; 20 };
+            [f(1), f(2)];
+        "#;
+
+        let mut evaluator = Evaluator::new(input);
+        let result = evaluator.eval_program().unwrap();
+        let strings = |values: &[&str]| {
+            values
+                .iter()
+                .map(|value| Object::StringValue(value.to_string()))
+                .collect()
+        };
+
+        assert_eq!(
+            result[1],
+            Object::ArrayValue(strings(&["zero", "one", "minus one", "many", "many"]))
+        );
+        assert_eq!(result[2], Object::IntegerValue(2));
+        assert_eq!(result[3], Object::UnitValue);
+        assert_eq!(
+            result[5],
+            Object::ArrayValue(vec![Object::IntegerValue(10), Object::IntegerValue(20)])
+        );
+    }
+
+    #[test]
+    fn eval_do_expression() {
+        let input = r#"
+            let a = 1;
+            let b = do {
+                let tmp = a + 1;
+                tmp * 10
+            };
+            b;
+            let tmp = 5;
+            do { let tmp = 6; tmp };
+            tmp;
+            do { 1 } + do { 2 };
+            let f = fn() { do { return 3; }; 4 };
+            f();
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(&result[2], &Object::IntegerValue(20));
+        assert_eq!(&result[4], &Object::IntegerValue(6));
+        assert_eq!(&result[5], &Object::IntegerValue(5));
+        assert_eq!(&result[6], &Object::IntegerValue(3));
+        assert_eq!(&result[8], &Object::IntegerValue(3));
+
+        // bindings don't outlive the block
+        let mut evaluator = Evaluator::new("do { let tmp = 1; }; tmp;");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::IdentifierNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn eval_for_statement() {
+        let input = r#"
+            let find = fn(arr, target) {
+                for x in arr {
+                    if x == target {
+                        return x * 10;
+                    }
+                }
+
+                -1
+            };
+
+            let firstKey = fn(map) {
+                for key in map {
+                    return key;
+                }
+            };
+
+            find([1, 2, 3], 2);
+            find([1, 2, 3], 4);
+            firstKey({"b": 1, "a": 2});
+            for x in [] { x }
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(&result[2], &Object::IntegerValue(20));
+        assert_eq!(&result[3], &Object::IntegerValue(-1));
+        assert_eq!(&result[4], &Object::StringValue("a".to_owned()));
+        assert_eq!(&result[5], &Object::UnitValue);
+
+        let mut evaluator = Evaluator::new("for x in 5 { x }");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn eval_loop_controls() {
+        let input = r#"
+            let stopsEarly = fn() {
+                for x in [1, 2, 3] {
+                    if x == 2 {
+                        break;
+                    }
+
+                    if x == 3 {
+                        return "not stopped";
+                    }
+                }
+
+                "stopped"
+            };
+
+            let firstOdd = fn(arr) {
+                for x in arr {
+                    if x % 2 == 0 {
+                        continue;
+                    }
+
+                    return x;
+                }
+            };
+
+            stopsEarly();
+            firstOdd([2, 4, 5, 6]);
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(&result[2], &Object::StringValue("stopped".to_owned()));
+        assert_eq!(&result[3], &Object::IntegerValue(5));
+
+        let tests = vec![
+            ("break;", "Break statement used outside a loop"),
+            ("{ continue; }", "Continue statement used outside a loop"),
+            (
+                "let f = fn() { break; }; for x in [1] { f(); }",
+                "Break statement used outside a loop",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            assert_eq!(evaluator.eval_program().unwrap_err().to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn eval_loop_controls_in_values() {
+        let buffer = SharedBuffer::default();
+        let input = r#"
+            for i in range(3) {
+                let y = if i == 1 { break; } else { i };
+                println(y);
+            }
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        evaluator.set_writer(buffer.clone());
+        evaluator.eval_program().unwrap();
+        assert_eq!(buffer.contents(), "0\n");
+
+        let input = r#"
+            let total = 0;
+            for i in range(4) {
+                total = if i == 1 { continue; } else { total + i };
+                let f = fn(x) { x };
+                f(if i == 3 { break; } else { i });
+            }
+            total;
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = evaluator.eval_program().unwrap();
+        assert_eq!(result[2], Object::IntegerValue(5));
+    }
+
+    #[test]
+    fn eval_loop_controls_in_operands() {
+        let operands = [
+            "[1, STOP, 3]",
+            r#"{"a": STOP}"#,
+            "1 + STOP",
+            "STOP * 2",
+            "-STOP",
+            "[1, 2, 3][STOP]",
+            "[[1, 2, 3]][0][STOP]",
+            "!(STOP == 1)",
+            "if STOP == 1 { 1 } else { 2 }",
+        ];
+        for (control, expected) in [("continue", 2), ("break", 1)] {
+            for operand in operands {
+                let stop = format!("if i == 1 {{ {control}; }} else {{ i }}");
+                let input = format!(
+                    r#"
+                    let count = 0;
+                    for i in range(3) {{
+                        let v = {};
+                        count = count + 1;
+                    }}
+                    count;
+                    "#,
+                    operand.replace("STOP", &stop)
+                );
+                let mut evaluator = Evaluator::new(&input);
+                let result = evaluator.eval_program().unwrap();
+                assert_eq!(
+                    result[2],
+                    Object::IntegerValue(expected),
+                    "`{control}` in `{operand}`"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn eval_assign_statement() {
+        let input = r#"
+            let a = 2;
+            a = a + 2;
+            a;
+        "#;
         let mut evaluator = Evaluator::new(input);
         let result = &evaluator.eval_program().unwrap()[2];
         assert_eq!(result, &Object::IntegerValue(4));
+
+        let mut evaluator = Evaluator::new("b = 1;");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::IdentifierNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn eval_const_statement() {
+        let input = r#"
+            const a = 1;
+            let b = a + 1;
+            b = 3;
+            b;
+            const a = 2;
+            a;
+            let a = 3;
+            a = 4;
+            a;
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(&result[3], &Object::IntegerValue(3));
+        assert_eq!(&result[5], &Object::IntegerValue(2));
+        assert_eq!(&result[8], &Object::IntegerValue(4));
+
+        let tests = vec![
+            "const a = 1; a = 2;",
+            "const a = 1; a += 2;",
+            "const a = 1; { a = 2; }",
+            "const [a, b] = [1, 2]; b = 3;",
+            "const arr = [1]; arr[0] = 2;",
+        ];
+
+        for input in tests {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(
+                    evaluator.eval_program(),
+                    Err(EvalError::AssignmentToImmutable(_))
+                ),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn eval_forked_evaluators() {
+        let mut prelude = Evaluator::new("let double = fn(x) { x * 2 };");
+        prelude.eval_program().unwrap();
+
+        let mut first = prelude.fork("let x = double(1); x;");
+        let mut second = prelude.fork("let x = double(5); x;");
+        assert_eq!(&first.eval_program().unwrap()[1], &Object::IntegerValue(2));
+        assert_eq!(
+            &second.eval_program().unwrap()[1],
+            &Object::IntegerValue(10)
+        );
+
+        // forks don't leak bindings into each other or into the prelude
+        assert_eq!(
+            first.env.borrow().get("x").unwrap(),
+            Object::IntegerValue(2)
+        );
+        assert!(prelude.env.borrow().get("x").is_err());
+
+        // the prelude itself is frozen once forked
+        assert!(matches!(
+            prelude.eval_statement(Statement::VarStatement {
+                kind: TokenKind::Let,
+                name: "y".to_owned(),
+                value: Expression::IntegerLiteral(1),
+            }),
+            Err(EvalError::FrozenEnvironment(_))
+        ));
+
+        // forks can't re-assign the prelude bindings either
+        let mut fork = prelude.fork("double = 1;");
+        assert!(matches!(
+            fork.eval_program(),
+            Err(EvalError::FrozenEnvironment(_))
+        ));
+    }
+
+    #[test]
+    fn eval_assign_outer_scope() {
+        let input = r#"
+            let x = 1;
+            {
+                x = 2;
+            }
+            x;
+
+            let sum = 0;
+            for i in [1, 2, 3] {
+                sum += i;
+            }
+            sum;
+
+            let counter = 0;
+            let increment = fn() { counter += 1; };
+            increment();
+            increment();
+            counter;
+
+            let arr = [0, 0];
+            if true { arr[1] = 5; }
+            arr;
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(&result[2], &Object::IntegerValue(2));
+        assert_eq!(&result[5], &Object::IntegerValue(6));
+        assert_eq!(&result[10], &Object::IntegerValue(2));
+        assert_eq!(
+            &result[13],
+            &Object::ArrayValue(vec![Object::IntegerValue(0), Object::IntegerValue(5)])
+        );
+
+        // shadowed bindings are updated instead of the outer ones
+        let input = r#"
+            let x = 1;
+            {
+                let x = 2;
+                x = 3;
+            }
+            x;
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(&result[2], &Object::IntegerValue(1));
+    }
+
+    #[test]
+    fn eval_compound_assign_statement() {
+        let input = r#"
+            let a = 10;
+            a += 5;
+            a -= 1;
+            a *= 2;
+            a /= 4;
+            a %= 4;
+            a;
+
+            let s = "foo";
+            s += "bar";
+            s;
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(&result[6], &Object::IntegerValue(3));
+        assert_eq!(&result[9], &Object::StringValue("foobar".to_owned()));
+
+        let mut evaluator = Evaluator::new("b += 1;");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::IdentifierNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn eval_index_assign_statement() {
+        let input = r#"
+            let arr = [1, 2, 3];
+            arr[1] = 5;
+            arr;
+
+            let m = {"a": 1};
+            m["a"] = 2;
+            m["b"] = 3;
+            m;
+
+            let nested = [[1], {"x": 0}];
+            nested[0][0] = 4;
+            nested[1]["x"] = 5;
+            nested;
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(
+            &result[2],
+            &Object::ArrayValue(vec![
+                Object::IntegerValue(1),
+                Object::IntegerValue(5),
+                Object::IntegerValue(3),
+            ])
+        );
+        assert_eq!(
+            &result[6],
+            &Object::MapValue(BTreeMap::from([
+                (
+                    HashableObject::String("a".to_owned()),
+                    Object::IntegerValue(2)
+                ),
+                (
+                    HashableObject::String("b".to_owned()),
+                    Object::IntegerValue(3)
+                ),
+            ]))
+        );
+        assert_eq!(
+            &result[10],
+            &Object::ArrayValue(vec![
+                Object::ArrayValue(vec![Object::IntegerValue(4)]),
+                Object::MapValue(BTreeMap::from([(
+                    HashableObject::String("x".to_owned()),
+                    Object::IntegerValue(5)
+                )])),
+            ])
+        );
+
+        let mut evaluator = Evaluator::new("let arr = [1]; arr[1] = 2;");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::IndexOutOfBounds(1, 1))
+        ));
+    }
+
+    #[test]
+    fn eval_multiple_values_return() {
+        let input = r#"
+            let divmod = fn(a, b) {
+                return a / b, a % b;
+            };
+            let [q, r] = divmod(17, 5);
+            q;
+            r;
+            divmod(9, 3);
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(&result[2], &Object::IntegerValue(3));
+        assert_eq!(&result[3], &Object::IntegerValue(2));
+        assert_eq!(
+            &result[4],
+            &Object::ArrayValue(vec![Object::IntegerValue(3), Object::IntegerValue(0)])
+        );
+
+        let mut evaluator = Evaluator::new("let [a, b] = [1];");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::DestructuringMismatch(2, 1))
+        ));
+
+        let mut evaluator = Evaluator::new("let [a] = 1;");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn eval_not_callable() {
+        let tests = vec![
+            (
+                r#"let m = {"a": 1}; m(1);"#,
+                r#"Function not found: a map is not callable, use indexing (e.g. `map["key"]`) to get its values"#,
+            ),
+            (
+                "let arr = [1, 2]; arr(0);",
+                "Function not found: an array is not callable, use indexing (e.g. `array[0]`) to get its elements",
+            ),
+            (
+                "let x = 5; x();",
+                "Function not found: `5` cannot be called as a function",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let err = evaluator.eval_program().unwrap_err();
+            assert_eq!(err.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn eval_recursion_limit() {
+        let mut evaluator = Evaluator::new("let f = fn() { f() }; f();");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::RecursionLimitExceeded(DEFAULT_MAX_DEPTH))
+        ));
+
+        let input = r#"
+            let count = fn(n) { if n == 0 { 0 } else { 1 + count(n - 1) } };
+            count(10);
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        evaluator.set_max_depth(11);
+        assert_eq!(
+            &evaluator.eval_program().unwrap()[1],
+            &Object::IntegerValue(10)
+        );
+
+        let mut evaluator = Evaluator::new(input);
+        evaluator.set_max_depth(10);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::RecursionLimitExceeded(10))
+        ));
     }
 
     #[test]
@@ -815,70 +3221,51 @@ mod tests {
     }
 
     #[test]
-    fn eval_function_as_parameter() {
-        let input = r#"
-            let add = fn(x, y) { return x + y; };
-            let sub = fn(x, y) { return x - y; };
-            let applyFunc = fn(a, b, cb) {
-                cb(a, b)
-            };
-            applyFunc(2, 2, add);
-            applyFunc(10, 2, sub);
-        "#;
-        let mut evaluator = Evaluator::new(input);
-        let result = &evaluator.eval_program().unwrap();
-        assert_eq!(&result[3], &Object::IntegerValue(4));
-        assert_eq!(&result[4], &Object::IntegerValue(8));
-    }
-
-    #[test]
-    fn builtin_len() {
+    fn eval_return_stops_at_call_boundary() {
         let input = r#"
-            len("hello");
-            len("");
+            let f = fn() { return 1; };
+            let g = fn() { f(); 2 };
+            g();
         "#;
         let mut evaluator = Evaluator::new(input);
         let result = &evaluator.eval_program().unwrap();
-        assert_eq!(&result[0], &Object::IntegerValue(5));
-        assert_eq!(&result[1], &Object::IntegerValue(0));
+        assert_eq!(&result[2], &Object::IntegerValue(2));
     }
 
     #[test]
-    fn builtin_append() {
+    fn eval_non_tail_recursion() {
         let input = r#"
-            append([1, 2, 3], 100, 200);
+            let fib = fn(n) {
+                if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+            };
+            fib(15);
         "#;
         let mut evaluator = Evaluator::new(input);
         let result = &evaluator.eval_program().unwrap();
-        assert_eq!(
-            &result[0],
-            &Object::ArrayValue(vec![
-                Object::IntegerValue(1),
-                Object::IntegerValue(2),
-                Object::IntegerValue(3),
-                Object::IntegerValue(100),
-                Object::IntegerValue(200),
-            ])
-        );
+        assert_eq!(&result[1], &Object::IntegerValue(610));
     }
 
     #[test]
-    fn builtin_rest() {
+    fn eval_function_as_parameter() {
         let input = r#"
-            rest([1, 2, 3]);
+            let add = fn(x, y) { return x + y; };
+            let sub = fn(x, y) { return x - y; };
+            let applyFunc = fn(a, b, cb) {
+                cb(a, b)
+            };
+            applyFunc(2, 2, add);
+            applyFunc(10, 2, sub);
         "#;
         let mut evaluator = Evaluator::new(input);
         let result = &evaluator.eval_program().unwrap();
-        assert_eq!(
-            &result[0],
-            &Object::ArrayValue(vec![Object::IntegerValue(2), Object::IntegerValue(3)])
-        );
+        assert_eq!(&result[3], &Object::IntegerValue(4));
+        assert_eq!(&result[4], &Object::IntegerValue(8));
     }
 
     #[test]
     fn custom_map() {
         let input = r#"
-            let map = fn(arr, f) {
+            let my_map = fn(arr, f) {
                 let iter = fn(arr, accumulated) {
                     if len(arr) == 0 {
                         accumulated
@@ -892,7 +3279,7 @@ mod tests {
 
             let arr = [1, 2, 3, 4];
             let double = fn(x) { x * 2 };
-            map(arr, double);
+            my_map(arr, double);
         "#;
         let mut evaluator = Evaluator::new(input);
         let result = &evaluator.eval_program().unwrap();
@@ -910,7 +3297,7 @@ mod tests {
     #[test]
     fn custom_reduce() {
         let input = r#"
-            let reduce = fn(arr, initial, f) {
+            let my_reduce = fn(arr, initial, f) {
                 let iter = fn(arr, result) {
                     if len(arr) == 0 {
                         result
@@ -923,7 +3310,7 @@ mod tests {
             };
 
             let sum = fn(arr) {
-                return reduce(arr, 0, fn(initial, el) { initial + el });
+                return my_reduce(arr, 0, fn(initial, el) { initial + el });
             };
 
             sum([1, 2, 3, 4, 5]);
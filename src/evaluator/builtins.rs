@@ -0,0 +1,3049 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    ast::ParserError,
+    object::{BuiltinFunction, EvalError, HashableObject, Memoized, Object},
+    time::format_timestamp,
+};
+
+use super::{index_object, Evaluator};
+
+impl<'a> Evaluator<'a> {
+    /// Evaluates a built-in function against its already evaluated arguments.
+    pub(super) fn eval_builtin_function(
+        &mut self,
+        builtin: BuiltinFunction,
+        mut arguments: Vec<Object>,
+    ) -> Result<Object, EvalError> {
+        let obj = match builtin {
+            BuiltinFunction::Len => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                // unwrapping is fine, this element surely exist because of the previous check
+                let arg = arguments.first().unwrap();
+
+                let length: i32 = match arg {
+                    Object::StringValue(text) => text
+                        .len()
+                        .try_into()
+                        .map_err(ParserError::IntConversionError)?,
+
+                    Object::ArrayValue(objects) => objects
+                        .len()
+                        .try_into()
+                        .map_err(ParserError::IntConversionError)?,
+
+                    Object::BytesValue(bytes) => bytes
+                        .len()
+                        .try_into()
+                        .map_err(ParserError::IntConversionError)?,
+
+                    _ => {
+                        return Err(EvalError::UnsupportedArgumentType(format!(
+                            "`{}` only retrieves the length of strings, arrays and bytes",
+                            BuiltinFunction::Len
+                        )));
+                    }
+                };
+
+                Object::IntegerValue(length)
+            }
+
+            BuiltinFunction::Append => {
+                if arguments.len() < 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let (first, rest) = arguments.split_first_mut().unwrap();
+
+                if let Object::ArrayValue(objects) = first {
+                    self.check_collection_size(objects.len().saturating_add(rest.len()))?;
+                    objects.extend_from_slice(rest);
+                    // return a new array, rather than modifying the existing one
+                    Object::ArrayValue(objects.clone())
+                } else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{}` only works on arrays",
+                        BuiltinFunction::Append
+                    )));
+                }
+            }
+
+            BuiltinFunction::Rest => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                // unwrapping is fine, this element surely exist because of the previous check
+                let arg = arguments.first().unwrap();
+
+                if let Object::ArrayValue(objects) = arg {
+                    // return a new array, rather than modifying the existing one
+                    Object::ArrayValue(objects[1..].to_vec())
+                } else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{}` only works on arrays",
+                        BuiltinFunction::Rest
+                    )));
+                }
+            }
+
+            BuiltinFunction::Println => {
+                for arg in &arguments {
+                    self.write_output(format_args!("{arg}\n"))?;
+                }
+                Object::UnitValue
+            }
+
+            BuiltinFunction::Print => {
+                for arg in &arguments {
+                    self.write_output(format_args!("{arg}"))?;
+                }
+                Object::UnitValue
+            }
+
+            BuiltinFunction::Eprintln => {
+                for arg in &arguments {
+                    self.write_error_output(format_args!("{arg}\n"))?;
+                }
+                Object::UnitValue
+            }
+
+            BuiltinFunction::Eprint => {
+                for arg in &arguments {
+                    self.write_error_output(format_args!("{arg}"))?;
+                }
+                Object::UnitValue
+            }
+
+            BuiltinFunction::Debug => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                let value = arguments.remove(0);
+                self.write_output(format_args!("{value:#?}\n"))?;
+                value
+            }
+
+            BuiltinFunction::Bytes => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                let Object::ArrayValue(objects) = arguments.remove(0) else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{}` only builds bytes from an array of integers",
+                        BuiltinFunction::Bytes
+                    )));
+                };
+
+                let bytes = objects
+                    .into_iter()
+                    .map(|obj| match obj {
+                        Object::IntegerValue(value) => u8::try_from(value).map_err(|_| {
+                            EvalError::UnsupportedArgumentType(format!(
+                                "`{}` only accepts integers between 0 and 255, got {value}",
+                                BuiltinFunction::Bytes
+                            ))
+                        }),
+                        other => Err(EvalError::UnsupportedArgumentType(format!(
+                            "`{}` only accepts integers between 0 and 255, got {other}",
+                            BuiltinFunction::Bytes
+                        ))),
+                    })
+                    .collect::<Result<Vec<u8>, EvalError>>()?;
+
+                Object::BytesValue(bytes)
+            }
+
+            BuiltinFunction::FromUtf8 => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                let Object::BytesValue(bytes) = arguments.remove(0) else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{}` only decodes bytes",
+                        BuiltinFunction::FromUtf8
+                    )));
+                };
+
+                Object::StringValue(String::from_utf8(bytes)?)
+            }
+
+            BuiltinFunction::Base64Encode => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                let encoded = match &arguments[0] {
+                    Object::StringValue(text) => base64_encode(text.as_bytes()),
+                    Object::BytesValue(bytes) => base64_encode(bytes),
+                    _ => {
+                        return Err(EvalError::UnsupportedArgumentType(format!(
+                            "`{builtin}` only encodes strings and bytes"
+                        )));
+                    }
+                };
+
+                Object::StringValue(encoded)
+            }
+
+            BuiltinFunction::Base64Decode => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                let Object::StringValue(text) = &arguments[0] else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` only decodes strings"
+                    )));
+                };
+
+                // the decoded data could be anything, `from_utf8` turns it back into a string
+                Object::BytesValue(base64_decode(text).map_err(EvalError::InvalidBase64)?)
+            }
+
+            BuiltinFunction::Template => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let (Object::StringValue(text), Object::MapValue(map)) =
+                    (&arguments[0], &arguments[1])
+                else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{}` expects a string and a map",
+                        BuiltinFunction::Template
+                    )));
+                };
+
+                let rendered = render_template(text, map);
+                self.check_collection_size(rendered.len())?;
+
+                Object::StringValue(rendered)
+            }
+
+            BuiltinFunction::Matches => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let (Object::StringValue(text), Object::StringValue(pattern)) =
+                    (&arguments[0], &arguments[1])
+                else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{}` expects a string and a pattern string",
+                        BuiltinFunction::Matches
+                    )));
+                };
+
+                Object::BooleanValue(glob_match(text, pattern))
+            }
+
+            BuiltinFunction::Split => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let (Object::StringValue(text), Object::StringValue(separator)) =
+                    (&arguments[0], &arguments[1])
+                else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` expects a string and a string separator"
+                    )));
+                };
+
+                // an empty separator splits into characters, rather than also yielding
+                // empty strings at both ends like Rust's `split` does
+                let parts = if separator.is_empty() {
+                    text.chars()
+                        .map(|char| Object::StringValue(char.to_string()))
+                        .collect()
+                } else {
+                    text.split(separator.as_str())
+                        .map(|part| Object::StringValue(part.to_owned()))
+                        .collect()
+                };
+
+                Object::ArrayValue(parts)
+            }
+
+            BuiltinFunction::Range => {
+                let (start, end) = match arguments[..] {
+                    [Object::IntegerValue(end)] => (0, end),
+                    [Object::IntegerValue(start), Object::IntegerValue(end)] => (start, end),
+                    [_] | [_, _] => {
+                        return Err(EvalError::UnsupportedArgumentType(format!(
+                            "`{builtin}` expects integer bounds"
+                        )));
+                    }
+                    _ => return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8)),
+                };
+
+                // the difference is computed in 64 bits, since it can overflow an `i32`
+                let length = (i64::from(end) - i64::from(start)).max(0);
+                self.check_collection_size(usize::try_from(length).unwrap_or(usize::MAX))?;
+
+                Object::ArrayValue((start..end).map(Object::IntegerValue).collect())
+            }
+
+            BuiltinFunction::Get => {
+                if !(2..=3).contains(&arguments.len()) {
+                    // report the closest accepted number of arguments
+                    let expected = arguments.len().clamp(2, 3) as u8;
+                    return Err(EvalError::FunctionCallWrongArity(
+                        expected,
+                        arguments.len() as u8,
+                    ));
+                }
+
+                let default = if arguments.len() == 3 {
+                    arguments.pop().unwrap()
+                } else {
+                    Object::NilValue
+                };
+                let index = arguments.pop().unwrap();
+                let collection = arguments.pop().unwrap();
+
+                // only missing elements give the default, invalid indices are still errors
+                match index_object(collection, index) {
+                    Ok(obj) => obj,
+                    Err(EvalError::ValueNotFound(_) | EvalError::IndexOutOfBounds(..)) => default,
+                    Err(err) => return Err(err),
+                }
+            }
+
+            BuiltinFunction::Window => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let (Object::ArrayValue(objects), Object::IntegerValue(size)) =
+                    (&arguments[0], &arguments[1])
+                else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` expects an array and an integer size"
+                    )));
+                };
+
+                let size = match usize::try_from(*size) {
+                    Ok(size) if size > 0 => size,
+                    _ => {
+                        return Err(EvalError::UnsupportedArgumentType(format!(
+                            "`{builtin}` sizes must be positive, got {size}"
+                        )));
+                    }
+                };
+
+                // windows overlap, so their elements add up much faster than the array's
+                let windows = objects.len().saturating_sub(size - 1);
+                self.check_collection_size(windows.saturating_mul(size))?;
+
+                // `windows` gives nothing when the size exceeds the length of the array
+                Object::ArrayValue(
+                    objects
+                        .windows(size)
+                        .map(|window| Object::ArrayValue(window.to_vec()))
+                        .collect(),
+                )
+            }
+
+            BuiltinFunction::Repeat => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let count = match arguments[1] {
+                    Object::IntegerValue(count) if count >= 0 => count as usize,
+                    _ => {
+                        return Err(EvalError::UnsupportedArgumentType(format!(
+                            "`{builtin}` expects a non-negative integer count"
+                        )));
+                    }
+                };
+
+                match &arguments[0] {
+                    Object::StringValue(text) => {
+                        self.check_collection_size(text.len().saturating_mul(count))?;
+                        Object::StringValue(text.repeat(count))
+                    }
+                    Object::ArrayValue(objects) => {
+                        self.check_collection_size(objects.len().saturating_mul(count))?;
+                        let len = objects.len() * count;
+                        Object::ArrayValue(objects.iter().cycle().take(len).cloned().collect())
+                    }
+                    _ => {
+                        return Err(EvalError::UnsupportedArgumentType(format!(
+                            "`{builtin}` only repeats strings and arrays"
+                        )));
+                    }
+                }
+            }
+
+            BuiltinFunction::Type => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                Object::StringValue(arguments[0].type_name().to_owned())
+            }
+
+            BuiltinFunction::Str => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                let text = arguments[0].to_raw_string();
+                self.check_collection_size(text.len())?;
+
+                Object::StringValue(text)
+            }
+
+            BuiltinFunction::Int => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                let value = match &arguments[0] {
+                    Object::StringValue(text) => {
+                        text.parse::<i32>().map_err(ParserError::ParseIntError)?
+                    }
+                    Object::IntegerValue(value) => *value,
+                    Object::BooleanValue(value) => i32::from(*value),
+                    _ => {
+                        return Err(EvalError::UnsupportedArgumentType(format!(
+                            "`{builtin}` only converts strings, integers and booleans"
+                        )));
+                    }
+                };
+
+                Object::IntegerValue(value)
+            }
+
+            BuiltinFunction::Join => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let (Object::ArrayValue(objects), Object::StringValue(separator)) =
+                    (&arguments[0], &arguments[1])
+                else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` expects an array and a string separator"
+                    )));
+                };
+
+                // strings are joined as they are, any other element is stringified
+                let parts: Vec<String> = objects.iter().map(Object::to_raw_string).collect();
+
+                // checked before joining, since joining is what multiplies the size of the parts
+                let separators = separator
+                    .len()
+                    .saturating_mul(parts.len().saturating_sub(1));
+                let length = parts
+                    .iter()
+                    .fold(separators, |length, part| length.saturating_add(part.len()));
+                self.check_collection_size(length)?;
+
+                Object::StringValue(parts.join(separator))
+            }
+
+            BuiltinFunction::CharAt => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let (Object::StringValue(text), Object::IntegerValue(index)) =
+                    (&arguments[0], &arguments[1])
+                else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` expects a string and an integer"
+                    )));
+                };
+
+                // unlike indexing, out of range positions (negative ones too) aren't an error
+                let char = usize::try_from(*index)
+                    .ok()
+                    .and_then(|index| text.chars().nth(index));
+
+                match char {
+                    Some(char) => Object::StringValue(char.to_string()),
+                    None => Object::UnitValue,
+                }
+            }
+
+            BuiltinFunction::Fit => {
+                if !(2..=3).contains(&arguments.len()) {
+                    // report the closest accepted number of arguments
+                    let expected = arguments.len().clamp(2, 3) as u8;
+                    return Err(EvalError::FunctionCallWrongArity(
+                        expected,
+                        arguments.len() as u8,
+                    ));
+                }
+
+                let ellipsis = match arguments.get(2) {
+                    Some(Object::StringValue(ellipsis)) => ellipsis.as_str(),
+                    Some(_) => {
+                        return Err(EvalError::UnsupportedArgumentType(format!(
+                            "`{builtin}` expects the ellipsis to be a string"
+                        )));
+                    }
+                    None => "",
+                };
+
+                let (Object::StringValue(text), Object::IntegerValue(width)) =
+                    (&arguments[0], &arguments[1])
+                else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` expects a string and an integer width"
+                    )));
+                };
+
+                let Ok(width) = usize::try_from(*width) else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` widths can't be negative, got {width}"
+                    )));
+                };
+
+                // widths are measured in characters, not bytes
+                let len = text.chars().count();
+
+                if len <= width {
+                    self.check_collection_size(text.len().saturating_add(width - len))?;
+                    Object::StringValue(format!("{text}{}", " ".repeat(width - len)))
+                } else {
+                    // the ellipsis takes part of the width, and is cut too when it's wider
+                    let ellipsis_len = ellipsis.chars().count().min(width);
+                    let fitted = text
+                        .chars()
+                        .take(width - ellipsis_len)
+                        .chain(ellipsis.chars().take(ellipsis_len))
+                        .collect();
+
+                    Object::StringValue(fitted)
+                }
+            }
+
+            BuiltinFunction::Memoize => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                let function = arguments.remove(0);
+
+                if !function.is_callable() {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{}` only works on functions",
+                        BuiltinFunction::Memoize
+                    )));
+                }
+
+                Object::MemoizedValue(Memoized {
+                    function: Box::new(function),
+                    cache: Rc::new(RefCell::new(HashMap::new())),
+                })
+            }
+
+            BuiltinFunction::Map => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let function = arguments.pop().unwrap();
+
+                let (Object::ArrayValue(objects), true) =
+                    (arguments.remove(0), function.is_callable())
+                else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{}` expects an array and a function",
+                        BuiltinFunction::Map
+                    )));
+                };
+
+                let mapped = objects
+                    .into_iter()
+                    .map(|obj| self.call_function(function.clone(), vec![obj]))
+                    .collect::<Result<Vec<Object>, EvalError>>()?;
+
+                Object::ArrayValue(mapped)
+            }
+
+            BuiltinFunction::Reduce => {
+                if arguments.len() != 3 {
+                    return Err(EvalError::FunctionCallWrongArity(3, arguments.len() as u8));
+                }
+
+                let function = arguments.pop().unwrap();
+                let initial = arguments.pop().unwrap();
+
+                let (Object::ArrayValue(objects), true) =
+                    (arguments.remove(0), function.is_callable())
+                else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{}` expects an array, an initial value and a function",
+                        BuiltinFunction::Reduce
+                    )));
+                };
+
+                objects.into_iter().try_fold(initial, |accumulated, obj| {
+                    self.call_function(function.clone(), vec![accumulated, obj])
+                })?
+            }
+
+            BuiltinFunction::Scan => {
+                if arguments.len() != 3 {
+                    return Err(EvalError::FunctionCallWrongArity(3, arguments.len() as u8));
+                }
+
+                let function = arguments.pop().unwrap();
+                let mut accumulated = arguments.pop().unwrap();
+
+                let (Object::ArrayValue(objects), true) =
+                    (arguments.remove(0), function.is_callable())
+                else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{}` expects an array, an initial value and a function",
+                        BuiltinFunction::Scan
+                    )));
+                };
+
+                let mut scanned = Vec::with_capacity(objects.len());
+
+                for obj in objects {
+                    accumulated = self.call_function(function.clone(), vec![accumulated, obj])?;
+                    scanned.push(accumulated.clone());
+                }
+
+                Object::ArrayValue(scanned)
+            }
+
+            BuiltinFunction::Filter => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let function = arguments.pop().unwrap();
+
+                let (Object::ArrayValue(objects), true) =
+                    (arguments.remove(0), function.is_callable())
+                else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{}` expects an array and a function",
+                        BuiltinFunction::Filter
+                    )));
+                };
+
+                let mut filtered = vec![];
+
+                for obj in objects {
+                    match self.call_function(function.clone(), vec![obj.clone()])? {
+                        Object::BooleanValue(true) => filtered.push(obj),
+                        Object::BooleanValue(false) => {}
+                        other => {
+                            return Err(EvalError::TypeMismatch(format!(
+                                "`{}` predicates must return booleans, got `{other}`",
+                                BuiltinFunction::Filter
+                            )))
+                        }
+                    }
+                }
+
+                Object::ArrayValue(filtered)
+            }
+
+            BuiltinFunction::Partition => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let function = arguments.pop().unwrap();
+
+                let (Object::ArrayValue(objects), true) =
+                    (arguments.remove(0), function.is_callable())
+                else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` expects an array and a function"
+                    )));
+                };
+
+                let mut matching = vec![];
+                let mut rest = vec![];
+
+                for obj in objects {
+                    match self.call_function(function.clone(), vec![obj.clone()])? {
+                        Object::BooleanValue(true) => matching.push(obj),
+                        Object::BooleanValue(false) => rest.push(obj),
+                        other => {
+                            return Err(EvalError::TypeMismatch(format!(
+                                "`{builtin}` predicates must return booleans, got `{other}`"
+                            )))
+                        }
+                    }
+                }
+
+                Object::ArrayValue(vec![Object::ArrayValue(matching), Object::ArrayValue(rest)])
+            }
+
+            BuiltinFunction::SortBy => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let comparator = arguments.pop().unwrap();
+
+                let (Object::ArrayValue(objects), true) =
+                    (arguments.remove(0), comparator.is_callable())
+                else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` expects an array and a comparator function"
+                    )));
+                };
+
+                Object::ArrayValue(self.sort_by(objects, &comparator)?)
+            }
+
+            BuiltinFunction::GroupBy => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let function = arguments.pop().unwrap();
+
+                let (Object::ArrayValue(objects), true) =
+                    (arguments.remove(0), function.is_callable())
+                else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{}` expects an array and a function",
+                        BuiltinFunction::GroupBy
+                    )));
+                };
+
+                let mut groups: BTreeMap<HashableObject, Vec<Object>> = BTreeMap::new();
+
+                for obj in objects {
+                    let key = self.call_function(function.clone(), vec![obj.clone()])?;
+                    let key = HashableObject::try_from(key).map_err(|other| {
+                        EvalError::TypeMismatch(format!(
+                            "`{}` keys must be integers, booleans or strings, got `{other}`",
+                            BuiltinFunction::GroupBy
+                        ))
+                    })?;
+
+                    // elements keep their original order inside each group
+                    groups.entry(key).or_default().push(obj);
+                }
+
+                Object::MapValue(
+                    groups
+                        .into_iter()
+                        .map(|(key, group)| (key, Object::ArrayValue(group)))
+                        .collect(),
+                )
+            }
+
+            BuiltinFunction::Keys | BuiltinFunction::Values => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                let Object::MapValue(map) = arguments.remove(0) else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` only works on maps"
+                    )));
+                };
+
+                // maps are sorted by key, so that values line up with their keys
+                let objects = if builtin == BuiltinFunction::Keys {
+                    map.into_keys().map(Object::from).collect()
+                } else {
+                    map.into_values().collect()
+                };
+
+                Object::ArrayValue(objects)
+            }
+
+            BuiltinFunction::Delete => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let (Object::MapValue(mut map), Ok(key)) = (
+                    arguments.remove(0),
+                    HashableObject::try_from(arguments.remove(0)),
+                ) else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` expects a map and an integer, boolean or string key"
+                    )));
+                };
+
+                // the argument is already a copy, so the original map is left untouched
+                map.remove(&key);
+
+                Object::MapValue(map)
+            }
+
+            BuiltinFunction::Pick | BuiltinFunction::Omit => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let (Object::MapValue(map), Object::ArrayValue(objects)) =
+                    (arguments.remove(0), &arguments[0])
+                else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` expects a map and an array of keys"
+                    )));
+                };
+
+                let mut keys = vec![];
+                for obj in objects {
+                    let Ok(key) = HashableObject::try_from(obj.clone()) else {
+                        return Err(EvalError::UnsupportedArgumentType(format!(
+                            "`{builtin}` keys must be integers, booleans or strings, got `{obj}`"
+                        )));
+                    };
+                    keys.push(key);
+                }
+
+                // missing keys are simply ignored
+                let pick = builtin == BuiltinFunction::Pick;
+                let map = map
+                    .into_iter()
+                    .filter(|(key, _)| keys.contains(key) == pick)
+                    .collect();
+
+                Object::MapValue(map)
+            }
+
+            BuiltinFunction::MapValues | BuiltinFunction::MapKeys => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let function = arguments.pop().unwrap();
+
+                let (Object::MapValue(map), true) = (arguments.remove(0), function.is_callable())
+                else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` expects a map and a function"
+                    )));
+                };
+
+                // the function is called in the (sorted) order of the keys
+                let mut mapped = BTreeMap::new();
+
+                for (key, value) in map {
+                    if builtin == BuiltinFunction::MapValues {
+                        let value = self.call_function(function.clone(), vec![value])?;
+                        mapped.insert(key, value);
+                        continue;
+                    }
+
+                    let key = self.call_function(function.clone(), vec![Object::from(key)])?;
+                    let key = HashableObject::try_from(key).map_err(|other| {
+                        EvalError::TypeMismatch(format!(
+                            "`{builtin}` keys must be integers, booleans or strings, got `{other}`"
+                        ))
+                    })?;
+
+                    if mapped.contains_key(&key) {
+                        return Err(EvalError::DuplicateMapKey(key.to_string()));
+                    }
+
+                    mapped.insert(key, value);
+                }
+
+                Object::MapValue(mapped)
+            }
+
+            BuiltinFunction::Invert => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                let Object::MapValue(map) = arguments.remove(0) else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` only works on maps"
+                    )));
+                };
+
+                let mut inverted = BTreeMap::new();
+
+                for (key, value) in map {
+                    let Object::StringValue(value) = value else {
+                        return Err(EvalError::UnsupportedArgumentType(format!(
+                            "`{builtin}` turns values into keys, so they must be strings, got `{value}`"
+                        )));
+                    };
+
+                    let value = HashableObject::String(value);
+                    if inverted.contains_key(&value) {
+                        return Err(EvalError::DuplicateMapKey(value.to_string()));
+                    }
+
+                    inverted.insert(value, Object::from(key));
+                }
+
+                Object::MapValue(inverted)
+            }
+
+            BuiltinFunction::MergeWith => {
+                if arguments.len() != 3 {
+                    return Err(EvalError::FunctionCallWrongArity(3, arguments.len() as u8));
+                }
+
+                let function = arguments.pop().unwrap();
+                let right = arguments.pop().unwrap();
+                let left = arguments.pop().unwrap();
+
+                let (Object::MapValue(mut merged), Object::MapValue(right)) = (left, right) else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{}` only works on two maps",
+                        BuiltinFunction::MergeWith
+                    )));
+                };
+
+                // conflicts are resolved in the (sorted) order of the keys
+                for (key, value) in right {
+                    let value = match merged.remove(&key) {
+                        Some(existing) => {
+                            self.call_function(function.clone(), vec![existing, value])?
+                        }
+                        None => value,
+                    };
+
+                    merged.insert(key, value);
+                }
+
+                Object::MapValue(merged)
+            }
+
+            BuiltinFunction::IndexOf | BuiltinFunction::LastIndexOf => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let last = builtin == BuiltinFunction::LastIndexOf;
+
+                let position = match (&arguments[0], &arguments[1]) {
+                    (Object::ArrayValue(objects), needle) => {
+                        let mut positions = objects.iter().enumerate();
+                        if last {
+                            positions.rfind(|(_, obj)| *obj == needle)
+                        } else {
+                            positions.find(|(_, obj)| *obj == needle)
+                        }
+                        .map(|(i, _)| i)
+                    }
+
+                    // strings are searched for a substring, its position is counted in characters
+                    (Object::StringValue(text), Object::StringValue(pattern)) => if last {
+                        text.rfind(pattern.as_str())
+                    } else {
+                        text.find(pattern.as_str())
+                    }
+                    .map(|byte| text[..byte].chars().count()),
+
+                    _ => {
+                        return Err(EvalError::UnsupportedArgumentType(format!(
+                            "`{builtin}` only searches elements of arrays and substrings of strings"
+                        )));
+                    }
+                };
+
+                let index = match position {
+                    Some(position) => {
+                        i32::try_from(position).map_err(ParserError::IntConversionError)?
+                    }
+                    None => -1,
+                };
+
+                Object::IntegerValue(index)
+            }
+
+            BuiltinFunction::Contains => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let found = match (&arguments[0], &arguments[1]) {
+                    (Object::MapValue(map), key) => match HashableObject::try_from(key.clone()) {
+                        Ok(key) => map.contains_key(&key),
+                        Err(_) => {
+                            return Err(EvalError::UnsupportedArgumentType(format!(
+                                "`{builtin}` only checks integer, boolean or string keys of maps"
+                            )));
+                        }
+                    },
+                    (Object::ArrayValue(objects), needle) => objects.contains(needle),
+                    _ => {
+                        return Err(EvalError::UnsupportedArgumentType(format!(
+                            "`{builtin}` only checks keys of maps and elements of arrays"
+                        )));
+                    }
+                };
+
+                Object::BooleanValue(found)
+            }
+
+            BuiltinFunction::First | BuiltinFunction::Last => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                let first = builtin == BuiltinFunction::First;
+
+                let element = match arguments.remove(0) {
+                    Object::ArrayValue(mut objects) => {
+                        if first {
+                            objects.into_iter().next()
+                        } else {
+                            objects.pop()
+                        }
+                    }
+                    Object::StringValue(text) => {
+                        let ch = if first {
+                            text.chars().next()
+                        } else {
+                            text.chars().next_back()
+                        };
+                        ch.map(|ch| Object::StringValue(ch.to_string()))
+                    }
+                    _ => {
+                        return Err(EvalError::UnsupportedArgumentType(format!(
+                            "`{builtin}` only works on arrays and strings"
+                        )));
+                    }
+                };
+
+                element.ok_or_else(|| {
+                    EvalError::EmptyCollection(format!("`{builtin}` needs at least one element"))
+                })?
+            }
+
+            BuiltinFunction::NowSeconds => {
+                if !arguments.is_empty() {
+                    return Err(EvalError::FunctionCallWrongArity(0, arguments.len() as u8));
+                }
+
+                // a clock set before the epoch gives negative timestamps
+                let timestamp = match SystemTime::now().duration_since(UNIX_EPOCH) {
+                    Ok(elapsed) => elapsed.as_secs() as i64,
+                    Err(err) => -(err.duration().as_secs() as i64),
+                };
+
+                Object::TimeValue(timestamp)
+            }
+
+            BuiltinFunction::Time => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                let Object::IntegerValue(seconds) = arguments[0] else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` expects the number of seconds since the Unix epoch"
+                    )));
+                };
+
+                Object::TimeValue(seconds.into())
+            }
+
+            BuiltinFunction::FormatTime => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let (Object::TimeValue(timestamp), Object::StringValue(format)) =
+                    (&arguments[0], &arguments[1])
+                else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` expects a time and a format string"
+                    )));
+                };
+
+                Object::StringValue(format_timestamp(*timestamp, format))
+            }
+
+            BuiltinFunction::Version => {
+                if !arguments.is_empty() {
+                    return Err(EvalError::FunctionCallWrongArity(0, arguments.len() as u8));
+                }
+
+                Object::StringValue(crate::VERSION.to_owned())
+            }
+
+            BuiltinFunction::Cfg => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                let Object::StringValue(flag) = &arguments[0] else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{}` only checks flags named by a string",
+                        BuiltinFunction::Cfg
+                    )));
+                };
+
+                Object::BooleanValue(self.flags.contains(flag))
+            }
+
+            BuiltinFunction::FormatNumber => {
+                let (number, separator) = match &arguments[..] {
+                    [Object::IntegerValue(number)] => (*number, ","),
+                    [Object::IntegerValue(number), Object::StringValue(separator)] => {
+                        (*number, separator.as_str())
+                    }
+                    [_] | [_, _] => {
+                        return Err(EvalError::UnsupportedArgumentType(format!(
+                            "`{builtin}` expects an integer and an optional string separator"
+                        )));
+                    }
+                    _ => return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8)),
+                };
+
+                Object::StringValue(format_number(number, separator))
+            }
+
+            BuiltinFunction::IsBuiltin => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                let Object::StringValue(name) = &arguments[0] else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` only checks names given as strings"
+                    )));
+                };
+
+                // bindings shadowing a built-in don't change the answer,
+                // and built-in values like `None` aren't functions
+                Object::BooleanValue(matches!(
+                    BuiltinFunction::lookup_function(name),
+                    Ok(Object::BuiltinValue(_))
+                ))
+            }
+
+            BuiltinFunction::Some | BuiltinFunction::Ok | BuiltinFunction::Err => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                Object::tagged(&builtin.to_string(), arguments.pop())
+            }
+
+            BuiltinFunction::IsSome | BuiltinFunction::IsNone => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                let is_some = match arguments[0].as_tagged() {
+                    Some(("Some", _)) => true,
+                    Some(("None", _)) => false,
+                    _ => {
+                        return Err(EvalError::UnsupportedArgumentType(format!(
+                            "`{builtin}` only works on `Some` and `None`"
+                        )))
+                    }
+                };
+
+                Object::BooleanValue(is_some == (builtin == BuiltinFunction::IsSome))
+            }
+
+            BuiltinFunction::Unwrap | BuiltinFunction::UnwrapOr => {
+                let arity = if builtin == BuiltinFunction::Unwrap {
+                    1
+                } else {
+                    2
+                };
+                if arguments.len() != arity {
+                    return Err(EvalError::FunctionCallWrongArity(
+                        arity as u8,
+                        arguments.len() as u8,
+                    ));
+                }
+
+                let default = if arity == 2 { arguments.pop() } else { None };
+                let wrapped = arguments.pop().unwrap();
+
+                match (wrapped.as_tagged(), default) {
+                    (Some(("Some" | "Ok", Some(value))), _) => value.clone(),
+                    (Some(_), Some(default)) => default,
+                    (Some(("Err", Some(error))), None) => {
+                        return Err(EvalError::UnwrapFailed(format!("`Err({error})`")))
+                    }
+                    (Some((tag, _)), None) => {
+                        return Err(EvalError::UnwrapFailed(format!("`{tag}`")))
+                    }
+                    (None, _) => {
+                        return Err(EvalError::UnsupportedArgumentType(format!(
+                            "`{builtin}` only works on `Some`, `None`, `Ok` and `Err`"
+                        )))
+                    }
+                }
+            }
+
+            BuiltinFunction::Abs => {
+                if arguments.len() != 1 {
+                    return Err(EvalError::FunctionCallWrongArity(1, arguments.len() as u8));
+                }
+
+                let Object::IntegerValue(value) = arguments[0] else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` only works on integers"
+                    )));
+                };
+
+                // `abs(i32::MIN)` doesn't fit in an `i32`
+                Object::IntegerValue(
+                    value
+                        .checked_abs()
+                        .ok_or_else(|| EvalError::IntegerOverflow(format!("abs({value})")))?,
+                )
+            }
+
+            BuiltinFunction::Min | BuiltinFunction::Max => {
+                if arguments.len() < 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                // integers and strings have a natural order, but they can't be compared together
+                let comparable = match &arguments[0] {
+                    Object::IntegerValue(_) => arguments
+                        .iter()
+                        .all(|arg| matches!(arg, Object::IntegerValue(_))),
+                    Object::StringValue(_) => arguments
+                        .iter()
+                        .all(|arg| matches!(arg, Object::StringValue(_))),
+                    _ => false,
+                };
+
+                if !comparable {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` only compares either integers or strings"
+                    )));
+                }
+
+                let cmp = |a: &&Object, b: &&Object| match (a, b) {
+                    (Object::IntegerValue(a), Object::IntegerValue(b)) => a.cmp(b),
+                    (Object::StringValue(a), Object::StringValue(b)) => a.cmp(b),
+                    _ => unreachable!("arguments were checked to have the same type"),
+                };
+
+                let found = if builtin == BuiltinFunction::Min {
+                    arguments.iter().min_by(cmp)
+                } else {
+                    arguments.iter().max_by(cmp)
+                };
+
+                // unwrapping is fine, there are at least two arguments
+                found.unwrap().clone()
+            }
+
+            BuiltinFunction::Gcd | BuiltinFunction::Lcm => {
+                if arguments.len() != 2 {
+                    return Err(EvalError::FunctionCallWrongArity(2, arguments.len() as u8));
+                }
+
+                let (Object::IntegerValue(a), Object::IntegerValue(b)) =
+                    (&arguments[0], &arguments[1])
+                else {
+                    return Err(EvalError::UnsupportedArgumentType(format!(
+                        "`{builtin}` only works on integers"
+                    )));
+                };
+
+                let overflow = || EvalError::IntegerOverflow(format!("{builtin}({a}, {b})"));
+                let gcd = gcd(a.unsigned_abs(), b.unsigned_abs());
+
+                let result = if builtin == BuiltinFunction::Gcd {
+                    gcd
+                } else {
+                    // `lcm(0, 0)` is `0`, like any other `lcm` with a zero operand
+                    a.unsigned_abs()
+                        .checked_div(gcd)
+                        .unwrap_or(0)
+                        .checked_mul(b.unsigned_abs())
+                        .ok_or_else(overflow)?
+                };
+
+                Object::IntegerValue(i32::try_from(result).map_err(|_| overflow())?)
+            }
+        };
+
+        Ok(obj)
+    }
+
+    /// Stable merge sort calling back `comparator`, which can fail unlike the closures
+    /// accepted by `slice::sort_by` (that may also panic on inconsistent orderings).
+    fn sort_by(
+        &mut self,
+        mut objects: Vec<Object>,
+        comparator: &Object,
+    ) -> Result<Vec<Object>, EvalError> {
+        if objects.len() <= 1 {
+            return Ok(objects);
+        }
+
+        let right = objects.split_off(objects.len() / 2);
+        let left = self.sort_by(objects, comparator)?;
+        let right = self.sort_by(right, comparator)?;
+
+        let mut sorted = Vec::with_capacity(left.len() + right.len());
+        let mut left = left.into_iter().peekable();
+        let mut right = right.into_iter().peekable();
+
+        while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+            // taking from the right only when it strictly comes first keeps the sort stable
+            let args = vec![r.clone(), l.clone()];
+            let right_first = match self.call_function(comparator.clone(), args)? {
+                Object::IntegerValue(ordering) => ordering < 0,
+                Object::BooleanValue(less) => less,
+                other => {
+                    return Err(EvalError::TypeMismatch(format!(
+                        "`{}` comparators must return integers or booleans, got `{other}`",
+                        BuiltinFunction::SortBy
+                    )))
+                }
+            };
+
+            // unwrapping is fine, both elements were just peeked
+            if right_first {
+                sorted.push(right.next().unwrap());
+            } else {
+                sorted.push(left.next().unwrap());
+            }
+        }
+
+        sorted.extend(left);
+        sorted.extend(right);
+
+        Ok(sorted)
+    }
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `bytes` with the standard base64 alphabet, padding the result with `=`.
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    // every group of 3 bytes (24 bits) becomes 4 characters of 6 bits each
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+            group | u32::from(byte) << (16 - 8 * i)
+        });
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let sextet = (group >> (18 - 6 * i)) & 0b11_1111;
+                encoded.push(char::from(BASE64_ALPHABET[sextet as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Decodes padded, standard base64 text, rejecting any other character or a wrong length.
+fn base64_decode(text: &str) -> Result<Vec<u8>, String> {
+    let text = text.as_bytes();
+    if !text.len().is_multiple_of(4) {
+        return Err(format!(
+            "the length must be a multiple of 4, got {}",
+            text.len()
+        ));
+    }
+
+    let mut decoded = Vec::with_capacity(text.len() / 4 * 3);
+
+    for (n, chunk) in text.chunks(4).enumerate() {
+        let is_last = n == text.len() / 4 - 1;
+        // padding is only allowed at the end, in place of the last one or two characters
+        let padding = chunk.iter().rev().take_while(|&&ch| ch == b'=').count();
+        if padding > 2 || (padding > 0 && !is_last) {
+            return Err("unexpected padding".to_owned());
+        }
+
+        let mut group = 0u32;
+        for (i, &ch) in chunk[..4 - padding].iter().enumerate() {
+            let Some(sextet) = BASE64_ALPHABET.iter().position(|&symbol| symbol == ch) else {
+                return Err(format!("unexpected character `{}`", char::from(ch)));
+            };
+            group |= (sextet as u32) << (18 - 6 * i);
+        }
+
+        let bytes = group.to_be_bytes();
+        decoded.extend_from_slice(&bytes[1..4 - padding]);
+    }
+
+    Ok(decoded)
+}
+
+/// Greatest common divisor through Euclid's algorithm.
+/// Results are never negative, since they are computed on the absolute values.
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
+
+/// Formats an integer with `separator` between every group of three digits, e.g. `1,234,567`.
+fn format_number(number: i32, separator: &str) -> String {
+    let digits = number.unsigned_abs().to_string();
+    let mut formatted = String::new();
+
+    if number < 0 {
+        formatted.push('-');
+    }
+
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push_str(separator);
+        }
+        formatted.push(digit);
+    }
+
+    formatted
+}
+
+/// Matches the whole `text` against a glob `pattern`, where `*` matches any sequence
+/// of characters (even an empty one) and `?` matches exactly one character.
+fn glob_match(text: &str, pattern: &str) -> bool {
+    let text: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+
+    let (mut t, mut p) = (0, 0);
+    // position of the last `*` in the pattern and of the text it was matched against,
+    // used to backtrack when the rest of the pattern fails to match
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&ch) if ch == '?' || ch == text[t] => {
+                t += 1;
+                p += 1;
+            }
+            _ => match star {
+                // let the last `*` consume one more character and retry
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    // the text is over, so only trailing `*` can be left in the pattern
+    pattern[p..].iter().all(|&ch| ch == '*')
+}
+
+/// Replaces every `{key}` placeholder with the matching value of `map`.
+/// Placeholders without a matching key are left untouched, while `{{` and `}}` produce literal braces.
+fn render_template(text: &str, map: &BTreeMap<HashableObject, Object>) -> String {
+    let mut rendered = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                rendered.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                rendered.push('}');
+            }
+            '{' => {
+                let mut key = String::new();
+                let mut closed = false;
+
+                for ch in chars.by_ref() {
+                    if ch == '}' {
+                        closed = true;
+                        break;
+                    }
+                    key.push(ch);
+                }
+
+                match map.get(&HashableObject::String(key.clone())) {
+                    Some(value) if closed => rendered.push_str(&value.to_raw_string()),
+                    _ => {
+                        rendered.push('{');
+                        rendered.push_str(&key);
+                        if closed {
+                            rendered.push('}');
+                        }
+                    }
+                }
+            }
+            _ => rendered.push(ch),
+        }
+    }
+
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::SharedBuffer;
+    use super::*;
+
+    #[test]
+    fn builtin_len() {
+        let input = r#"
+            len("hello");
+            len("");
+            len(bytes([1, 2, 3]));
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(&result[0], &Object::IntegerValue(5));
+        assert_eq!(&result[1], &Object::IntegerValue(0));
+        assert_eq!(&result[2], &Object::IntegerValue(3));
+    }
+
+    #[test]
+    fn builtin_append() {
+        let input = r#"
+            append([1, 2, 3], 100, 200);
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(
+            &result[0],
+            &Object::ArrayValue(vec![
+                Object::IntegerValue(1),
+                Object::IntegerValue(2),
+                Object::IntegerValue(3),
+                Object::IntegerValue(100),
+                Object::IntegerValue(200),
+            ])
+        );
+    }
+
+    #[test]
+    fn builtin_rest() {
+        let input = r#"
+            rest([1, 2, 3]);
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(
+            &result[0],
+            &Object::ArrayValue(vec![Object::IntegerValue(2), Object::IntegerValue(3)])
+        );
+    }
+
+    #[test]
+    fn builtin_bytes() {
+        let input = r#"
+            let b = bytes([104, 105, 0]);
+            b;
+            b[1];
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(&result[1], &Object::BytesValue(vec![104, 105, 0]));
+        assert_eq!(result[1].to_string(), r#"b"hi\x00""#);
+        assert_eq!(&result[2], &Object::IntegerValue(105));
+
+        let mut evaluator = Evaluator::new("bytes([1, 256]);");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::UnsupportedArgumentType(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_from_utf8() {
+        let input = r#"
+            from_utf8(bytes([104, 105]));
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(&result[0], &Object::StringValue("hi".to_owned()));
+
+        let mut evaluator = Evaluator::new("from_utf8(bytes([255, 254]));");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::InvalidUtf8(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_template() {
+        let input = r#"
+            template("Hello {name}, you are {age}", {"name": "World", "age": 42});
+            template("Hello {name}", {});
+            template("{{name}} is {name}", {"name": "Qalo"});
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(
+            &result[0],
+            &Object::StringValue("Hello World, you are 42".to_owned())
+        );
+        assert_eq!(&result[1], &Object::StringValue("Hello {name}".to_owned()));
+        assert_eq!(
+            &result[2],
+            &Object::StringValue("{name} is Qalo".to_owned())
+        );
+    }
+
+    #[test]
+    fn builtin_split() {
+        let tests = vec![
+            (r#"split("a,b,c", ",")"#, vec!["a", "b", "c"]),
+            (r#"split("abc", "")"#, vec!["a", "b", "c"]),
+            (r#"split("a,,b,", ",")"#, vec!["a", "", "b", ""]),
+            (r#"split("a::b", "::")"#, vec!["a", "b"]),
+            (r#"split("", ",")"#, vec![""]),
+            (r#"split("", "")"#, vec![]),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            let expected = expected
+                .into_iter()
+                .map(|part| Object::StringValue(part.to_owned()))
+                .collect();
+            assert_eq!(result, &Object::ArrayValue(expected), "{input}");
+        }
+
+        let mut evaluator = Evaluator::new(r#"split("a,b", 1)"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::UnsupportedArgumentType(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_range() {
+        let tests = vec![
+            ("range(3)", vec![0, 1, 2]),
+            ("range(2, 5)", vec![2, 3, 4]),
+            ("range(5, 2)", vec![]),
+            ("range(-2, 1)", vec![-2, -1, 0]),
+            ("range(0)", vec![]),
+            ("range(-3)", vec![]),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            let expected = expected.into_iter().map(Object::IntegerValue).collect();
+            assert_eq!(result, &Object::ArrayValue(expected), "{input}");
+        }
+
+        let mut evaluator = Evaluator::new("range(-2147483647 - 1, 2147483647)");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::CollectionTooLarge(_))
+        ));
+
+        let mut evaluator = Evaluator::new(r#"range("3")"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::UnsupportedArgumentType(_))
+        ));
+
+        let mut evaluator = Evaluator::new("range(1, 2, 3)");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::FunctionCallWrongArity(2, 3))
+        ));
+    }
+
+    #[test]
+    fn builtin_type() {
+        let tests = vec![
+            ("type(1)", "integer"),
+            ("type(true)", "boolean"),
+            (r#"type("a")"#, "string"),
+            ("type([1])", "array"),
+            (r#"type({"a": 1})"#, "map"),
+            ("type(bytes([1]))", "bytes"),
+            ("type(time(0))", "time"),
+            ("type(fn(x) { x })", "function"),
+            ("type(len)", "function"),
+            ("type(memoize(fn(x) { x }))", "function"),
+            ("type(())", "unit"),
+            ("type(println())", "unit"),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, &Object::StringValue(expected.to_owned()), "{input}");
+        }
+    }
+
+    #[test]
+    fn builtin_str() {
+        let tests = vec![
+            ("str(42)", "42"),
+            ("str(true)", "true"),
+            ("str([1, 2])", "[1, 2]"),
+            (r#"str("text")"#, "text"),
+            (r#"str(["a"])"#, r#"["a"]"#),
+            ("str(())", "()"),
+            (r#""n = " + str(-1)"#, "n = -1"),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, &Object::StringValue(expected.to_owned()), "{input}");
+        }
+
+        let mut evaluator = Evaluator::new("str(1, 2)");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::FunctionCallWrongArity(1, 2))
+        ));
+    }
+
+    #[test]
+    fn builtin_int() {
+        let tests = vec![
+            (r#"int("42")"#, 42),
+            (r#"int("-7")"#, -7),
+            ("int(5)", 5),
+            ("int(true)", 1),
+            ("int(false)", 0),
+            (r#"int(str(12)) + 1"#, 13),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, &Object::IntegerValue(expected), "{input}");
+        }
+
+        for input in [r#"int("oops")"#, r#"int("")"#, r#"int("2147483648")"#] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(
+                    evaluator.eval_program(),
+                    Err(EvalError::ParsingError(ParserError::ParseIntError(_)))
+                ),
+                "{input}"
+            );
+        }
+
+        let mut evaluator = Evaluator::new("int([1])");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::UnsupportedArgumentType(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_join() {
+        let tests = vec![
+            (r#"join(["a", "b", "c"], ",")"#, "a,b,c"),
+            (r#"join([1, 2, 3], "-")"#, "1-2-3"),
+            (r#"join(["a", true, [1, "b"]], " ")"#, r#"a true [1, "b"]"#),
+            (r#"join(split("a,b", ","), "")"#, "ab"),
+            (r#"join([], ",")"#, ""),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, &Object::StringValue(expected.to_owned()), "{input}");
+        }
+
+        let mut evaluator = Evaluator::new(r#"join("abc", ",")"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::UnsupportedArgumentType(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_char_at() {
+        let tests = vec![
+            (
+                r#"char_at("hello", 0)"#,
+                Object::StringValue("h".to_owned()),
+            ),
+            (
+                r#"char_at("hello", 4)"#,
+                Object::StringValue("o".to_owned()),
+            ),
+            (r#"char_at("hello", 5)"#, Object::UnitValue),
+            (r#"char_at("hello", -1)"#, Object::UnitValue),
+            (r#"char_at("", 0)"#, Object::UnitValue),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, &expected, "{input}");
+        }
+
+        let mut evaluator = Evaluator::new(r#"char_at(["h"], 0)"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::UnsupportedArgumentType(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_keys_values() {
+        let input = r#"
+            let m = {"b": 2, "a": 1, "c": 3};
+            keys(m);
+            values(m);
+            keys({});
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(
+            &result[1],
+            &Object::ArrayValue(vec![
+                Object::StringValue("a".to_owned()),
+                Object::StringValue("b".to_owned()),
+                Object::StringValue("c".to_owned()),
+            ])
+        );
+        assert_eq!(
+            &result[2],
+            &Object::ArrayValue(vec![
+                Object::IntegerValue(1),
+                Object::IntegerValue(2),
+                Object::IntegerValue(3),
+            ])
+        );
+        assert_eq!(&result[3], &Object::ArrayValue(vec![]));
+
+        for input in ["keys([1])", "values(1)"] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(
+                    evaluator.eval_program(),
+                    Err(EvalError::UnsupportedArgumentType(_))
+                ),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn builtin_invert() {
+        let input = r#"
+            invert({"a": "1", "b": "2"}) == {"1": "a", "2": "b"};
+            invert({1: "one", true: "yes"});
+            invert({});
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(result[0], Object::BooleanValue(true));
+        assert_eq!(result[1].to_string(), r#"{"one": 1, "yes": true}"#);
+        assert_eq!(result[2], Object::MapValue(BTreeMap::new()));
+
+        let mut evaluator = Evaluator::new(r#"invert({"a": "x", "b": "x"})"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::DuplicateMapKey(key)) if key == "\"x\""
+        ));
+
+        for input in [r#"invert({"a": 1})"#, "invert([1])"] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(
+                    evaluator.eval_program(),
+                    Err(EvalError::UnsupportedArgumentType(_))
+                ),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn builtin_delete() {
+        let input = r#"
+            let m = {"a": 1, "b": 2};
+            delete(m, "a");
+            delete(m, "c");
+            m;
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(
+            &result[1],
+            &Object::MapValue(BTreeMap::from([(
+                HashableObject::String("b".to_owned()),
+                Object::IntegerValue(2)
+            )]))
+        );
+
+        let original = Object::MapValue(BTreeMap::from([
+            (
+                HashableObject::String("a".to_owned()),
+                Object::IntegerValue(1),
+            ),
+            (
+                HashableObject::String("b".to_owned()),
+                Object::IntegerValue(2),
+            ),
+        ]));
+        assert_eq!(&result[2], &original);
+        assert_eq!(&result[3], &original);
+
+        let mut evaluator = Evaluator::new(r#"delete([1], "a")"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::UnsupportedArgumentType(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_pick_omit() {
+        let input = r#"
+            let m = {"a": 1, "b": 2, "c": 3};
+            pick(m, ["a", "b", "x"]);
+            omit(m, ["c", "x"]);
+            pick(m, []);
+            omit(m, []);
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+
+        let a_b = Object::MapValue(BTreeMap::from([
+            (
+                HashableObject::String("a".to_owned()),
+                Object::IntegerValue(1),
+            ),
+            (
+                HashableObject::String("b".to_owned()),
+                Object::IntegerValue(2),
+            ),
+        ]));
+        assert_eq!(&result[1], &a_b);
+        assert_eq!(&result[2], &a_b);
+        assert_eq!(&result[3], &Object::MapValue(BTreeMap::new()));
+        assert_eq!(
+            &result[4],
+            &Object::MapValue(BTreeMap::from([
+                (
+                    HashableObject::String("a".to_owned()),
+                    Object::IntegerValue(1)
+                ),
+                (
+                    HashableObject::String("b".to_owned()),
+                    Object::IntegerValue(2)
+                ),
+                (
+                    HashableObject::String("c".to_owned()),
+                    Object::IntegerValue(3)
+                ),
+            ]))
+        );
+
+        for input in [r#"pick([1], ["a"])"#, r#"omit({"a": 1}, [[1]])"#] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(
+                    evaluator.eval_program(),
+                    Err(EvalError::UnsupportedArgumentType(_))
+                ),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn builtin_map_values_keys() {
+        let input = r#"
+            let m = {"a": 1, "b": 2};
+            map_values(m, fn(v) { v * 2 });
+            map_keys(m, fn(k) { "key_" + k });
+            map_values({}, fn(v) { v });
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(
+            &result[1],
+            &Object::MapValue(BTreeMap::from([
+                (
+                    HashableObject::String("a".to_owned()),
+                    Object::IntegerValue(2)
+                ),
+                (
+                    HashableObject::String("b".to_owned()),
+                    Object::IntegerValue(4)
+                ),
+            ]))
+        );
+        assert_eq!(
+            &result[2],
+            &Object::MapValue(BTreeMap::from([
+                (
+                    HashableObject::String("key_a".to_owned()),
+                    Object::IntegerValue(1)
+                ),
+                (
+                    HashableObject::String("key_b".to_owned()),
+                    Object::IntegerValue(2)
+                ),
+            ]))
+        );
+        assert_eq!(&result[3], &Object::MapValue(BTreeMap::new()));
+
+        let mut evaluator = Evaluator::new(r#"map_keys({"a": 1, "b": 2}, fn(k) { "same" });"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::DuplicateMapKey(key)) if key == "\"same\""
+        ));
+
+        let mut evaluator = Evaluator::new(r#"map_keys({"a": 1}, fn(k) { [k] });"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_merge_with() {
+        let input = r#"
+            let a = {"x": 1, "y": 2};
+            let b = {"y": 3, "z": 4};
+            merge_with(a, b, fn(l, r) { l + r });
+            merge_with(a, {}, fn(l, r) { l + r });
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(
+            &result[2],
+            &Object::MapValue(BTreeMap::from([
+                (
+                    HashableObject::String("x".to_owned()),
+                    Object::IntegerValue(1)
+                ),
+                (
+                    HashableObject::String("y".to_owned()),
+                    Object::IntegerValue(5)
+                ),
+                (
+                    HashableObject::String("z".to_owned()),
+                    Object::IntegerValue(4)
+                ),
+            ]))
+        );
+        assert_eq!(
+            &result[3],
+            &Object::MapValue(BTreeMap::from([
+                (
+                    HashableObject::String("x".to_owned()),
+                    Object::IntegerValue(1)
+                ),
+                (
+                    HashableObject::String("y".to_owned()),
+                    Object::IntegerValue(2)
+                ),
+            ]))
+        );
+
+        let mut evaluator = Evaluator::new("merge_with([1], {}, fn(l, r) { l });");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::UnsupportedArgumentType(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_index_of() {
+        let tests = vec![
+            ("index_of([10, 20, 30, 20], 20)", 1),
+            ("last_index_of([10, 20, 30, 20], 20)", 3),
+            ("index_of([10, 20, 30, 20], 40)", -1),
+            ("last_index_of([], 40)", -1),
+            (r#"index_of([1, "a", [2]], [2])"#, 2),
+            (r#"index_of("hello", "l")"#, 2),
+            (r#"last_index_of("hello", "l")"#, 3),
+            (r#"index_of("hello", "x")"#, -1),
+            (r#"index_of("hello", "")"#, 0),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, &Object::IntegerValue(expected), "{input}");
+        }
+
+        let mut evaluator = Evaluator::new(r#"index_of("hello", 1)"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::UnsupportedArgumentType(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_contains() {
+        let tests = vec![
+            (r#"contains({"a": 1}, "a")"#, true),
+            (r#"contains({"a": 1}, "b")"#, false),
+            (r#"contains({"a": 1}, 1)"#, false),
+            (r#"contains({1: "a", true: "b"}, true)"#, true),
+            ("contains([1, 2, 3], 2)", true),
+            ("contains([1, 2, 3], 4)", false),
+            (r#"contains([[1], "a"], [1])"#, true),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, &Object::BooleanValue(expected), "{input}");
+        }
+
+        for input in [r#"contains("abc", "a")"#, r#"contains({"a": 1}, [1])"#] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(matches!(
+                evaluator.eval_program(),
+                Err(EvalError::UnsupportedArgumentType(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn builtin_cfg() {
+        let input = r#"
+            cfg("verbose");
+            cfg("debug");
+            if cfg("verbose") { "loud" } else { "quiet" };
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        evaluator.set_flag("verbose");
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(&result[0], &Object::BooleanValue(true));
+        assert_eq!(&result[1], &Object::BooleanValue(false));
+        assert_eq!(&result[2], &Object::StringValue("loud".to_owned()));
+
+        // forks inherit the flags
+        let mut fork = evaluator.fork(r#"cfg("verbose")"#);
+        assert_eq!(
+            &fork.eval_program().unwrap()[0],
+            &Object::BooleanValue(true)
+        );
+    }
+
+    #[test]
+    fn builtin_matches() {
+        let tests = vec![
+            (r#"matches("file.txt", "*.txt")"#, true),
+            (r#"matches("file.txt", "*.rs")"#, false),
+            (r#"matches("file.txt", "file.???")"#, true),
+            (r#"matches("file.txt", "file.??")"#, false),
+            (r#"matches("file.txt", "file.txt")"#, true),
+            (r#"matches("file.txt", "file")"#, false),
+            (r#"matches("a-b-c", "a*c")"#, true),
+            (r#"matches("abcbd", "a*b?")"#, true),
+            (r#"matches("abc", "*")"#, true),
+            (r#"matches("", "*")"#, true),
+            (r#"matches("", "?")"#, false),
+            (r#"matches("aaa", "a**a")"#, true),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, &Object::BooleanValue(expected), "{input}");
+        }
+    }
+
+    #[test]
+    fn builtin_first_last() {
+        let tests = vec![
+            ("first([10, 20, 30])", Object::IntegerValue(10)),
+            ("last([10, 20, 30])", Object::IntegerValue(30)),
+            (
+                "last([[1, 2]])",
+                Object::ArrayValue(vec![Object::IntegerValue(1), Object::IntegerValue(2)]),
+            ),
+            (r#"first("hello")"#, Object::StringValue("h".to_owned())),
+            (r#"last("hello")"#, Object::StringValue("o".to_owned())),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, &expected, "{input}");
+        }
+
+        for input in ["first([])", "last([])", r#"first("")"#] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(evaluator.eval_program(), Err(EvalError::EmptyCollection(_))),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn builtin_map() {
+        let input = r#"
+            map([1, 2, 3], fn(x) { x * 2 });
+            map([], fn(x) { x * 2 });
+            map(["a", "bc"], len);
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(
+            &result[0],
+            &Object::ArrayValue(vec![
+                Object::IntegerValue(2),
+                Object::IntegerValue(4),
+                Object::IntegerValue(6),
+            ])
+        );
+        assert_eq!(&result[1], &Object::ArrayValue(vec![]));
+        assert_eq!(
+            &result[2],
+            &Object::ArrayValue(vec![Object::IntegerValue(1), Object::IntegerValue(2)])
+        );
+
+        for input in ["map(1, fn(x) { x })", "map([1], 2)"] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(
+                    evaluator.eval_program(),
+                    Err(EvalError::UnsupportedArgumentType(_))
+                ),
+                "{input}"
+            );
+        }
+
+        let mut evaluator = Evaluator::new("map([1])");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::FunctionCallWrongArity(2, 1))
+        ));
+    }
+
+    #[test]
+    fn builtin_reduce() {
+        let input = r#"
+            reduce([1, 2, 3, 4, 5], 0, fn(acc, el) { acc + el });
+            reduce([], 10, fn(acc, el) { acc + el });
+            reduce(["a", "b"], "", fn(acc, el) { el + acc });
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(&result[0], &Object::IntegerValue(15));
+        assert_eq!(&result[1], &Object::IntegerValue(10));
+        assert_eq!(&result[2], &Object::StringValue("ba".to_owned()));
+
+        let mut evaluator = Evaluator::new("reduce([1], 0, 1);");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::UnsupportedArgumentType(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_scan() {
+        let input = r#"
+            let add = fn(acc, el) { acc + el };
+            scan([1, 2, 3], 0, add);
+            scan([1, 2, 3], 10, add);
+            scan(["a", "b"], "", fn(acc, el) { el + acc });
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(
+            &result[1],
+            &Object::ArrayValue(vec![
+                Object::IntegerValue(1),
+                Object::IntegerValue(3),
+                Object::IntegerValue(6),
+            ])
+        );
+        assert_eq!(
+            &result[2],
+            &Object::ArrayValue(vec![
+                Object::IntegerValue(11),
+                Object::IntegerValue(13),
+                Object::IntegerValue(16),
+            ])
+        );
+        assert_eq!(
+            &result[3],
+            &Object::ArrayValue(vec![
+                Object::StringValue("a".to_owned()),
+                Object::StringValue("ba".to_owned()),
+            ])
+        );
+
+        // the initial value isn't part of the result
+        let mut evaluator = Evaluator::new("scan([], 10, fn(acc, el) { acc + el });");
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(&result[0], &Object::ArrayValue(vec![]));
+
+        for input in ["scan([1], 0, 1);", "scan(1, 0, fn(acc, el) { acc });"] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(
+                    evaluator.eval_program(),
+                    Err(EvalError::UnsupportedArgumentType(_))
+                ),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn builtin_filter() {
+        let input = r#"
+            let isEven = fn(x) { x % 2 == 0 };
+            filter([1, 2, 3, 4], isEven);
+            filter([], isEven);
+            filter(["a", "b", "a"], fn(x) { x == "a" });
+            filter(["debug", "verbose"], cfg);
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        evaluator.set_flag("verbose");
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(
+            &result[1],
+            &Object::ArrayValue(vec![Object::IntegerValue(2), Object::IntegerValue(4)])
+        );
+        assert_eq!(&result[2], &Object::ArrayValue(vec![]));
+        assert_eq!(
+            &result[3],
+            &Object::ArrayValue(vec![
+                Object::StringValue("a".to_owned()),
+                Object::StringValue("a".to_owned()),
+            ])
+        );
+        assert_eq!(
+            &result[4],
+            &Object::ArrayValue(vec![Object::StringValue("verbose".to_owned())])
+        );
+
+        let mut evaluator = Evaluator::new("filter([1, 2], fn(x) { x });");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_partition() {
+        let input = r#"
+            partition([1, 2, 3, 4, 5], fn(x) { x % 2 == 0 });
+            partition([], fn(x) { true });
+            let [long, short] = partition(["abc", "a"], fn(s) { len(s) > 2 });
+            short;
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(
+            &result[0],
+            &Object::ArrayValue(vec![
+                Object::ArrayValue(vec![Object::IntegerValue(2), Object::IntegerValue(4)]),
+                Object::ArrayValue(vec![
+                    Object::IntegerValue(1),
+                    Object::IntegerValue(3),
+                    Object::IntegerValue(5),
+                ]),
+            ])
+        );
+        assert_eq!(
+            &result[1],
+            &Object::ArrayValue(vec![Object::ArrayValue(vec![]), Object::ArrayValue(vec![])])
+        );
+        assert_eq!(
+            result.last().unwrap(),
+            &Object::ArrayValue(vec![Object::StringValue("a".to_owned())])
+        );
+
+        let mut evaluator = Evaluator::new("partition([1, 2], fn(x) { x });");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::TypeMismatch(_))
+        ));
+
+        let mut evaluator = Evaluator::new("partition(1, fn(x) { true });");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::UnsupportedArgumentType(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_sort_by() {
+        let input = r#"
+            let people = [
+                {"name": "a", "age": 30},
+                {"name": "b", "age": 40},
+                {"name": "c", "age": 30},
+                {"name": "d", "age": 20},
+            ];
+            let by_age_descending = sort_by(people, fn(x, y) { y["age"] - x["age"] });
+            map(by_age_descending, fn(person) { person["name"] });
+            sort_by([3, 1, 2], fn(x, y) { x < y });
+            let numbers = [5, 4, 3];
+            sort_by(numbers, fn(x, y) { x - y });
+            numbers;
+            sort_by([], fn(x, y) { 0 });
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+
+        let names = ["b", "a", "c", "d"]
+            .into_iter()
+            .map(|name| Object::StringValue(name.to_owned()))
+            .collect();
+        assert_eq!(&result[2], &Object::ArrayValue(names));
+
+        let integers = |values: &[i32]| {
+            Object::ArrayValue(values.iter().map(|v| Object::IntegerValue(*v)).collect())
+        };
+        assert_eq!(&result[3], &integers(&[1, 2, 3]));
+        assert_eq!(&result[5], &integers(&[3, 4, 5]));
+        assert_eq!(&result[6], &integers(&[5, 4, 3]));
+        assert_eq!(&result[7], &integers(&[]));
+
+        let mut evaluator = Evaluator::new(r#"sort_by([1, 2], fn(x, y) { "less" })"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_group_by() {
+        let input = r#"
+            group_by([1, 2, 3, 4, 5], fn(x) { if x % 2 == 0 { "even" } else { "odd" } });
+            group_by([], fn(x) { "any" });
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(
+            &result[0],
+            &Object::MapValue(BTreeMap::from([
+                (
+                    HashableObject::String("even".to_owned()),
+                    Object::ArrayValue(vec![Object::IntegerValue(2), Object::IntegerValue(4)])
+                ),
+                (
+                    HashableObject::String("odd".to_owned()),
+                    Object::ArrayValue(vec![
+                        Object::IntegerValue(1),
+                        Object::IntegerValue(3),
+                        Object::IntegerValue(5),
+                    ])
+                ),
+            ]))
+        );
+        assert_eq!(&result[1], &Object::MapValue(BTreeMap::new()));
+
+        let mut evaluator = Evaluator::new("group_by([1, 2], fn(x) { [x] });");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_time() {
+        let input = r#"
+            let t = time(1700000000);
+            t;
+            format_time(t, "%d/%m/%Y");
+            format_time(t + 60, "%H:%M:%S");
+            t - time(0);
+            now_seconds() > t;
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(&result[1], &Object::TimeValue(1_700_000_000));
+        assert_eq!(result[1].to_string(), "2023-11-14 22:13:20");
+        assert_eq!(&result[2], &Object::StringValue("14/11/2023".to_owned()));
+        assert_eq!(&result[3], &Object::StringValue("22:14:20".to_owned()));
+        assert_eq!(&result[4], &Object::IntegerValue(1_700_000_000));
+        assert_eq!(&result[5], &Object::BooleanValue(true));
+
+        for input in [r#"time("0")"#, "format_time(0, \"%Y\")", "now_seconds(1)"] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(evaluator.eval_program().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn builtin_version() {
+        let mut evaluator = Evaluator::new("version()");
+        let Object::StringValue(version) = &evaluator.eval_program().unwrap()[0] else {
+            panic!("expected a string");
+        };
+
+        let parts: Vec<&str> = version.split('.').collect();
+        assert_eq!(parts.len(), 3, "{version}");
+        assert!(
+            parts.iter().all(|part| part.parse::<u32>().is_ok()),
+            "{version}"
+        );
+    }
+
+    #[test]
+    fn builtin_memoize() {
+        let input = r#"
+            let fib = memoize(fn(n) {
+                if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+            });
+            fib(30);
+
+            let identity = memoize(fn(x) { x });
+            identity(4);
+            identity(4);
+            identity([4]);
+            identity;
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(&result[1], &Object::IntegerValue(832040));
+        assert_eq!(&result[3], &Object::IntegerValue(4));
+        assert_eq!(&result[4], &Object::IntegerValue(4));
+        assert_eq!(
+            &result[5],
+            &Object::ArrayValue(vec![Object::IntegerValue(4)])
+        );
+
+        // repeated calls hit the cache, while non-hashable arguments bypass it
+        let Object::MemoizedValue(Memoized { cache, .. }) = &result[6] else {
+            panic!("expected a memoized function, got {}", result[6]);
+        };
+        assert_eq!(cache.borrow().len(), 1);
+
+        // the function itself only runs once for repeated arguments
+        let input = r#"
+            let calls = 0;
+            let f = memoize(fn(x) { calls += 1; x });
+            f(1);
+            f(1);
+            f(1);
+            calls;
+            f(2);
+            calls;
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        let result = &evaluator.eval_program().unwrap();
+        assert_eq!(&result[5], &Object::IntegerValue(1));
+        assert_eq!(&result[7], &Object::IntegerValue(2));
+    }
+
+    #[test]
+    fn builtin_format_number() {
+        let tests = vec![
+            ("format_number(1234567)", "1,234,567"),
+            ("format_number(-1234567)", "-1,234,567"),
+            ("format_number(123)", "123"),
+            ("format_number(-100)", "-100"),
+            ("format_number(1000)", "1,000"),
+            ("format_number(0)", "0"),
+            (r#"format_number(1234567, ".")"#, "1.234.567"),
+            (r#"format_number(-2147483647 - 1, "_")"#, "-2_147_483_648"),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, &Object::StringValue(expected.to_owned()), "{input}");
+        }
+
+        let mut evaluator = Evaluator::new(r#"format_number("1")"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::UnsupportedArgumentType(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_base64() {
+        let tests = vec![
+            (r#"base64_encode("")"#, ""),
+            (r#"base64_encode("f")"#, "Zg=="),
+            (r#"base64_encode("fo")"#, "Zm8="),
+            (r#"base64_encode("foo")"#, "Zm9v"),
+            (r#"base64_encode("foobar")"#, "Zm9vYmFy"),
+            ("base64_encode(bytes([0, 255, 62, 63]))", "AP8+Pw=="),
+            (r#"from_utf8(base64_decode("Zm9vYmE="))"#, "fooba"),
+            (
+                r#"from_utf8(base64_decode(base64_encode("h\u{e9}llo, w\u{f6}rld!")))"#,
+                "h\u{e9}llo, w\u{f6}rld!",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, &Object::StringValue(expected.to_owned()), "{input}");
+        }
+
+        let mut evaluator = Evaluator::new(r#"base64_decode("AP8+Pw==")"#);
+        assert_eq!(
+            &evaluator.eval_program().unwrap()[0],
+            &Object::BytesValue(vec![0, 255, 62, 63])
+        );
+
+        for input in ["Zm9", "Zm9v!A==", "Zg==Zm9v", "Z===", "Zm9v\nYmFy"] {
+            let input = format!("base64_decode({input:?})");
+            let mut evaluator = Evaluator::new(&input);
+            assert!(
+                matches!(evaluator.eval_program(), Err(EvalError::InvalidBase64(_))),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn builtin_get() {
+        let tests = vec![
+            (r#"get({"a": 1}, "a")"#, Object::IntegerValue(1)),
+            (r#"get({"a": 1}, "b")"#, Object::NilValue),
+            (r#"get({"a": 1}, "b", 0)"#, Object::IntegerValue(0)),
+            (
+                r#"get({1: "x"}, 1, "y")"#,
+                Object::StringValue("x".to_owned()),
+            ),
+            ("get([1, 2], -1)", Object::IntegerValue(2)),
+            ("get([1, 2], 2)", Object::NilValue),
+            ("get([1, 2], -3, 0)", Object::IntegerValue(0)),
+            (r#"get("abc", 5, "")"#, Object::StringValue("".to_owned())),
+            ("get(bytes([7]), 0)", Object::IntegerValue(7)),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, &expected, "{input}");
+        }
+
+        let mut evaluator = Evaluator::new(r#"get([1], "a")"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::InvalidIndexType)
+        ));
+
+        let mut evaluator = Evaluator::new("get(1, 0)");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::InvalidIndexUsage)
+        ));
+
+        let mut evaluator = Evaluator::new("get([1])");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::FunctionCallWrongArity(2, 1))
+        ));
+
+        let mut evaluator = Evaluator::new("get([1], 0, 1, 2)");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::FunctionCallWrongArity(3, 4))
+        ));
+    }
+
+    #[test]
+    fn builtin_fit() {
+        let tests = vec![
+            (r#"fit("ab", 4)"#, "ab  "),
+            (r#"fit("abcd", 4)"#, "abcd"),
+            (r#"fit("abcdef", 4)"#, "abcd"),
+            (r#"fit("abcdef", 5, "...")"#, "ab..."),
+            (r#"fit("abcd", 4, "...")"#, "abcd"),
+            (r#"fit("abcdef", 2, "...")"#, ".."),
+            (r#"fit("abc", 0)"#, ""),
+            (r#"fit("é", 3)"#, "é  "),
+            (r#"fit("éééé", 3, "…")"#, "éé…"),
+            (r#"fit("héllo", 3, "...")"#, "..."),
+            (r#"fit("héllo", 4, "...")"#, "h..."),
+            (r#"fit("日本語", 2)"#, "日本"),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = evaluator.eval_program().unwrap();
+            assert_eq!(
+                result[0],
+                Object::StringValue(expected.to_owned()),
+                "{input}"
+            );
+        }
+
+        for input in [r#"fit("a", -1)"#, r#"fit(1, 2)"#, r#"fit("a", 2, 3)"#] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(
+                    evaluator.eval_program(),
+                    Err(EvalError::UnsupportedArgumentType(_))
+                ),
+                "{input}"
+            );
+        }
+
+        let mut evaluator = Evaluator::new(r#"fit("a")"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::FunctionCallWrongArity(2, 1))
+        ));
+
+        let mut evaluator = Evaluator::new(r#"fit("a", 1, "", "")"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::FunctionCallWrongArity(3, 4))
+        ));
+
+        let mut evaluator = Evaluator::new(r#"fit("a", 2147483647)"#);
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::CollectionTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_debug() {
+        let buffer = SharedBuffer::default();
+        let mut evaluator = Evaluator::new(r#"let x = debug([1, "a"]); println(len(x));"#);
+        evaluator.set_writer(buffer.clone());
+        evaluator.eval_program().unwrap();
+
+        let output = buffer.contents();
+        for variant in ["ArrayValue", "IntegerValue", "StringValue"] {
+            assert!(output.contains(variant), "{output}");
+        }
+        // the value is returned as is, and `println` goes through the same writer
+        assert!(output.ends_with(")\n2\n"), "{output}");
+
+        // closures referencing themselves don't recurse through their environment
+        let buffer = SharedBuffer::default();
+        let mut evaluator = Evaluator::new("fn f(n) { f(n) } debug(f);");
+        evaluator.set_writer(buffer.clone());
+        evaluator.eval_program().unwrap();
+        assert!(buffer.contents().contains("Closure"));
+
+        let mut evaluator = Evaluator::new("debug(1, 2)");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::FunctionCallWrongArity(1, 2))
+        ));
+    }
+
+    #[test]
+    fn builtin_eprintln() {
+        let (out, err) = (SharedBuffer::default(), SharedBuffer::default());
+        let input = r#"
+            println("out", 1);
+            eprintln("err", 2);
+            print("a");
+            eprint("b", "c");
+        "#;
+        let mut evaluator = Evaluator::new(input).with_writers(out.clone(), err.clone());
+        evaluator.eval_program().unwrap();
+        assert_eq!(out.contents(), "\"out\"\n1\n\"a\"");
+        assert_eq!(err.contents(), "\"err\"\n2\n\"b\"\"c\"");
+
+        // forks share the writers
+        let mut fork = evaluator.fork(r#"eprintln("fork");"#);
+        fork.eval_program().unwrap();
+        assert!(err.contents().ends_with("\"c\"\"fork\"\n"));
+    }
+
+    #[test]
+    fn builtin_repeat() {
+        let tests = vec![
+            (
+                r#"repeat("ab", 3)"#,
+                Object::StringValue("ababab".to_owned()),
+            ),
+            (r#"repeat("ab", 0)"#, Object::StringValue(String::new())),
+            (
+                "repeat([1, 2], 2)",
+                Object::ArrayValue(vec![
+                    Object::IntegerValue(1),
+                    Object::IntegerValue(2),
+                    Object::IntegerValue(1),
+                    Object::IntegerValue(2),
+                ]),
+            ),
+            ("repeat([], 5)", Object::ArrayValue(vec![])),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = evaluator.eval_program().unwrap();
+            assert_eq!(result[0], expected, "{input}");
+        }
+
+        for input in [r#"repeat("a", -1)"#, r#"repeat("a", "b")"#, "repeat(1, 2)"] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(
+                    evaluator.eval_program(),
+                    Err(EvalError::UnsupportedArgumentType(_))
+                ),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn builtin_max_collection_size() {
+        let tests = vec![
+            r#"repeat("abc", 4)"#,
+            "repeat([1, 2], 6)",
+            "append([1, 2, 3, 4, 5, 6, 7, 8, 9, 10], 11)",
+            "append([], 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11)",
+            r#"repeat("abcde", 2) + "f""#,
+            "range(11)",
+            "window(range(7), 2)",
+            "repeat([1], 2147483647)",
+            r#"join(["abcde", "fghij"], "-")"#,
+            r#"join(repeat(["abcde"], 3), "")"#,
+            r#"template("{x}{x}{x}", {"x": "abcd"})"#,
+            r#"str(["abcd", "efgh"])"#,
+        ];
+
+        for input in tests {
+            let mut evaluator = Evaluator::new(input);
+            evaluator.set_max_collection_size(10);
+            let err = evaluator.eval_program().unwrap_err();
+            assert!(matches!(err, EvalError::CollectionTooLarge(10)), "{input}");
+            assert_eq!(err.to_string(), "Collections are limited to 10 elements");
+        }
+
+        // reaching the maximum size is fine
+        let input = r#"
+            repeat("abcde", 2);
+            append(range(9), 10);
+            range(10);
+            window(range(6), 1);
+            join(["abcd", "efgh"], "-");
+            str([1, 2, 3]);
+        "#;
+        let mut evaluator = Evaluator::new(input);
+        evaluator.set_max_collection_size(10);
+        assert!(evaluator.eval_program().is_ok());
+    }
+
+    #[test]
+    fn builtin_window() {
+        let tests = vec![
+            ("window([1, 2, 3, 4], 2)", "[[1, 2], [2, 3], [3, 4]]"),
+            ("window([1, 2, 3], 1)", "[[1], [2], [3]]"),
+            ("window([1, 2, 3], 3)", "[[1, 2, 3]]"),
+            ("window([1, 2, 3], 4)", "[]"),
+            ("window([], 1)", "[]"),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result.to_string(), expected, "{input}");
+        }
+
+        for input in ["window([1], 0)", "window([1], -1)", r#"window("ab", 1)"#] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(
+                    evaluator.eval_program(),
+                    Err(EvalError::UnsupportedArgumentType(_))
+                ),
+                "{input}"
+            );
+        }
+
+        let mut evaluator = Evaluator::new("window(range(10000), 5000)");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::CollectionTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_is_builtin() {
+        let tests = vec![
+            (r#"is_builtin("len")"#, true),
+            (r#"is_builtin("is_builtin")"#, true),
+            (r#"is_builtin("lenght")"#, false),
+            (r#"let len = 1; is_builtin("len")"#, true),
+            (r#"is_builtin("Some")"#, true),
+            (r#"is_builtin("None")"#, false),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = evaluator.eval_program().unwrap();
+            assert_eq!(
+                result.last().unwrap(),
+                &Object::BooleanValue(expected),
+                "{input}"
+            );
+        }
+
+        let mut evaluator = Evaluator::new("is_builtin(len)");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::UnsupportedArgumentType(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_optionals() {
+        let tests = vec![
+            ("unwrap(Some(3))", Object::IntegerValue(3)),
+            ("unwrap_or(None, 5)", Object::IntegerValue(5)),
+            ("unwrap_or(Some(3), 5)", Object::IntegerValue(3)),
+            ("unwrap(Ok(1))", Object::IntegerValue(1)),
+            (r#"unwrap_or(Err("boom"), 2)"#, Object::IntegerValue(2)),
+            ("is_some(Some(nil))", Object::BooleanValue(true)),
+            ("is_some(None)", Object::BooleanValue(false)),
+            ("is_none(None)", Object::BooleanValue(true)),
+            ("Some(1) == Some(1)", Object::BooleanValue(true)),
+            ("Some(1) == Ok(1)", Object::BooleanValue(false)),
+            (r#"unwrap_or({"tag": "None"}, 0)"#, Object::IntegerValue(0)),
+            (
+                "str(Some([1]))",
+                Object::StringValue(r#"{"tag": "Some", "value": [1]}"#.to_owned()),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = evaluator.eval_program().unwrap();
+            assert_eq!(result.last().unwrap(), &expected, "{input}");
+        }
+
+        let errors = vec![
+            ("unwrap(None)", "Called `unwrap` on `None`"),
+            (
+                r#"unwrap(Err("boom"))"#,
+                r#"Called `unwrap` on `Err("boom")`"#,
+            ),
+        ];
+
+        for (input, expected) in errors {
+            let mut evaluator = Evaluator::new(input);
+            let err = evaluator.eval_program().unwrap_err();
+            assert!(matches!(err, EvalError::UnwrapFailed(_)), "{input}");
+            assert_eq!(err.to_string(), expected);
+        }
+
+        for input in ["unwrap(3)", "is_some(Ok(1))", r#"unwrap({"tag": "Some"})"#] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(
+                    evaluator.eval_program(),
+                    Err(EvalError::UnsupportedArgumentType(_))
+                ),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn builtin_names() {
+        let names = Evaluator::builtin_names();
+
+        assert!(names.contains(&"len"));
+        assert!(!names.contains(&"lenght"));
+        for name in names {
+            assert!(BuiltinFunction::lookup_function(name).is_ok(), "{name}");
+        }
+    }
+
+    #[test]
+    fn builtin_abs_min_max() {
+        let tests = vec![
+            ("abs(-5)", Object::IntegerValue(5)),
+            ("abs(5)", Object::IntegerValue(5)),
+            ("max(3, 9, 1)", Object::IntegerValue(9)),
+            ("min(3, 9, 1)", Object::IntegerValue(1)),
+            ("min(-1, 0)", Object::IntegerValue(-1)),
+            (r#"min("b", "a")"#, Object::StringValue("a".to_owned())),
+            (
+                r#"max("b", "a", "ab")"#,
+                Object::StringValue("b".to_owned()),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, &expected, "{input}");
+        }
+
+        let mut evaluator = Evaluator::new("abs(-2147483647 - 1)");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::IntegerOverflow(_))
+        ));
+
+        for input in [r#"max(1, "a")"#, "min([1], [2])", r#"abs("1")"#] {
+            let mut evaluator = Evaluator::new(input);
+            assert!(
+                matches!(
+                    evaluator.eval_program(),
+                    Err(EvalError::UnsupportedArgumentType(_))
+                ),
+                "{input}"
+            );
+        }
+
+        let mut evaluator = Evaluator::new("max(1)");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::FunctionCallWrongArity(2, 1))
+        ));
+    }
+
+    #[test]
+    fn builtin_gcd_lcm() {
+        let tests = vec![
+            ("gcd(12, 18)", 6),
+            ("gcd(-12, 18)", 6),
+            ("gcd(0, 5)", 5),
+            ("gcd(0, 0)", 0),
+            ("lcm(4, 6)", 12),
+            ("lcm(-4, 6)", 12),
+            ("lcm(0, 6)", 0),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new(input);
+            let result = &evaluator.eval_program().unwrap()[0];
+            assert_eq!(result, &Object::IntegerValue(expected), "{input}");
+        }
+
+        let mut evaluator = Evaluator::new("lcm(65536, 65537)");
+        assert!(matches!(
+            evaluator.eval_program(),
+            Err(EvalError::IntegerOverflow(_))
+        ));
+    }
+}
@@ -1,14 +1,16 @@
-use crate::token::{Token, TokenKind};
+use crate::token::{Position, Token, TokenKind};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Lexer<'a> {
     input: &'a str,
-    /// Current position in input (points to current char)
+    /// Current byte offset in input (points to current char)
     cur: usize,
-    /// Next position in input (after current char)
+    /// Next byte offset in input (after current char)
     next: usize,
     /// Current char under examination
     ch: char,
+    /// Position of the current char in the input
+    position: Position,
 }
 
 const EOF_CHAR: char = '\0';
@@ -20,6 +22,7 @@ impl<'a> Lexer<'a> {
             cur: 0,
             next: 0,
             ch: EOF_CHAR,
+            position: Position { line: 1, column: 0 },
         };
 
         lexer.eat_char();
@@ -29,24 +32,57 @@ impl<'a> Lexer<'a> {
 
     /// Give the next character.
     pub fn peek_char(&mut self) -> char {
-        if self.next >= self.input.chars().count() {
-            // reached EOF
-            EOF_CHAR
-        } else {
-            self.input.chars().nth(self.next).unwrap_or(EOF_CHAR)
-        }
+        self.peek_nth_char(0)
+    }
+
+    /// Give the character `n` positions after the next one.
+    pub fn peek_nth_char(&self, n: usize) -> char {
+        self.input[self.next..].chars().nth(n).unwrap_or(EOF_CHAR)
     }
 
     /// Retrieve the next character and advance position in the input string.
     pub fn eat_char(&mut self) {
+        if self.ch == '\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+
         self.ch = self.peek_char();
         self.cur = self.next;
-        self.next += 1;
+        // offsets are in bytes to slice the input, so they skip every byte of non-ASCII chars
+        self.next = (self.next + self.ch.len_utf8()).min(self.input.len());
     }
 
-    pub fn skip_whitespace(&mut self) {
-        while self.ch.is_whitespace() {
-            self.eat_char();
+    /// Skips whitespace and comments, either `// ...` up to the end of the line or `/* ... */`.
+    /// Gives back the position of a block comment that is never closed.
+    pub fn skip_whitespace_and_comments(&mut self) -> Option<Position> {
+        loop {
+            match (self.ch, self.peek_char()) {
+                (ch, _) if ch.is_whitespace() => self.eat_char(),
+                ('/', '/') => {
+                    while self.ch != '\n' && self.ch != EOF_CHAR {
+                        self.eat_char();
+                    }
+                }
+                ('/', '*') => {
+                    let start = self.position;
+                    self.eat_char();
+                    self.eat_char();
+
+                    while !(self.ch == '*' && self.peek_char() == '/') {
+                        if self.ch == EOF_CHAR {
+                            return Some(start);
+                        }
+                        self.eat_char();
+                    }
+
+                    self.eat_char();
+                    self.eat_char();
+                }
+                _ => return None,
+            }
         }
     }
 
@@ -71,24 +107,123 @@ impl<'a> Lexer<'a> {
         &self.input[start..self.cur]
     }
 
-    pub fn eat_string(&mut self) -> &str {
-        let start = self.cur + 1;
+    /// Reads a string literal, decoding its escape sequences.
+    /// Multiline literals (`"""..."""`) only end with three quotes, so they can contain single quotes.
+    /// The first invalid escape sequence is given back as an error, once the whole literal is read.
+    pub fn eat_string(&mut self, multiline: bool) -> Result<String, String> {
+        let mut string = String::new();
+        let mut invalid_escape = None;
 
         loop {
             self.eat_char();
 
-            // TODO: add support for escape characters
-            if self.ch == '"' || self.ch == EOF_CHAR {
-                break;
+            match self.ch {
+                '"' if !multiline => break,
+                '"' if self.peek_nth_char(0) == '"' && self.peek_nth_char(1) == '"' => {
+                    self.eat_char();
+                    self.eat_char();
+                    break;
+                }
+                EOF_CHAR => break,
+                '\\' => {
+                    self.eat_char();
+
+                    match self.eat_escape() {
+                        Ok(ch) => string.push(ch),
+                        Err(sequence) => {
+                            invalid_escape.get_or_insert(sequence);
+                        }
+                    }
+                }
+                ch => string.push(ch),
             }
         }
 
-        &self.input[start..self.cur]
+        match invalid_escape {
+            Some(sequence) => Err(sequence),
+            None => Ok(string),
+        }
+    }
+
+    /// Reads a raw string literal (`r"..."`), where backslashes have no special meaning.
+    pub fn eat_raw_string(&mut self) -> String {
+        let mut string = String::new();
+
+        loop {
+            self.eat_char();
+
+            match self.ch {
+                '"' | EOF_CHAR => break,
+                ch => string.push(ch),
+            }
+        }
+
+        string
+    }
+
+    /// Decodes the escape sequence whose backslash was just read.
+    fn eat_escape(&mut self) -> Result<char, String> {
+        let ch = match self.ch {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            '\\' => '\\',
+            '"' => '"',
+            // `\xNN` is limited to ASCII, since strings are UTF-8
+            'x' => {
+                let digits = self.eat_hex_digits(2);
+
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(byte) if digits.len() == 2 && byte.is_ascii() => char::from(byte),
+                    _ => return Err(format!("\\x{digits}")),
+                }
+            }
+            'u' => {
+                if self.peek_char() != '{' {
+                    return Err("\\u".to_owned());
+                }
+                self.eat_char();
+
+                let digits = self.eat_hex_digits(6);
+                if self.peek_char() != '}' {
+                    return Err(format!("\\u{{{digits}"));
+                }
+                self.eat_char();
+
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("\\u{{{digits}}}"))?
+            }
+            other => return Err(format!("\\{other}")),
+        };
+
+        Ok(ch)
+    }
+
+    /// Reads up to `max` hexadecimal digits, leaving the first non-digit unread.
+    fn eat_hex_digits(&mut self, max: usize) -> String {
+        let mut digits = String::new();
+
+        while digits.len() < max && self.peek_char().is_ascii_hexdigit() {
+            self.eat_char();
+            digits.push(self.ch);
+        }
+
+        digits
     }
 
     /// Retrieve the current token and advance position in the input string.
     pub fn next_token(&mut self) -> Token {
-        self.skip_whitespace();
+        if let Some(position) = self.skip_whitespace_and_comments() {
+            return Token {
+                kind: TokenKind::Illegal,
+                literal: "/*".to_owned(),
+                position,
+            };
+        }
+        let position = self.position;
 
         let token = match self.ch {
             '=' => {
@@ -97,11 +232,20 @@ impl<'a> Lexer<'a> {
                     Token {
                         kind: TokenKind::Equal,
                         literal: "==".to_owned(),
+                        position,
+                    }
+                } else if self.peek_char() == '>' {
+                    self.eat_char();
+                    Token {
+                        kind: TokenKind::FatArrow,
+                        literal: "=>".to_owned(),
+                        position,
                     }
                 } else {
                     Token {
                         kind: TokenKind::Assign,
                         literal: "=".to_owned(),
+                        position,
                     }
                 }
             }
@@ -111,11 +255,13 @@ impl<'a> Lexer<'a> {
                     Token {
                         kind: TokenKind::NotEqual,
                         literal: "!=".to_owned(),
+                        position,
                     }
                 } else {
                     Token {
                         kind: TokenKind::Bang,
                         literal: "!".to_owned(),
+                        position,
                     }
                 }
             }
@@ -125,11 +271,20 @@ impl<'a> Lexer<'a> {
                     Token {
                         kind: TokenKind::LessThanEqual,
                         literal: "<=".to_owned(),
+                        position,
+                    }
+                } else if self.peek_char() == '<' {
+                    self.eat_char();
+                    Token {
+                        kind: TokenKind::ShiftLeft,
+                        literal: "<<".to_owned(),
+                        position,
                     }
                 } else {
                     Token {
                         kind: TokenKind::LessThan,
                         literal: "<".to_owned(),
+                        position,
                     }
                 }
             }
@@ -139,11 +294,20 @@ impl<'a> Lexer<'a> {
                     Token {
                         kind: TokenKind::GreaterThanEqual,
                         literal: ">=".to_owned(),
+                        position,
+                    }
+                } else if self.peek_char() == '>' {
+                    self.eat_char();
+                    Token {
+                        kind: TokenKind::ShiftRight,
+                        literal: ">>".to_owned(),
+                        position,
                     }
                 } else {
                     Token {
                         kind: TokenKind::GreaterThan,
                         literal: ">".to_owned(),
+                        position,
                     }
                 }
             }
@@ -153,11 +317,13 @@ impl<'a> Lexer<'a> {
                     Token {
                         kind: TokenKind::AndAnd,
                         literal: "&&".to_owned(),
+                        position,
                     }
                 } else {
                     Token {
-                        kind: TokenKind::Illegal,
-                        literal: self.ch.to_string(),
+                        kind: TokenKind::Ampersand,
+                        literal: "&".to_owned(),
+                        position,
                     }
                 }
             }
@@ -167,81 +333,209 @@ impl<'a> Lexer<'a> {
                     Token {
                         kind: TokenKind::OrOr,
                         literal: "||".to_owned(),
+                        position,
+                    }
+                } else if self.peek_char() == '>' {
+                    self.eat_char();
+                    Token {
+                        kind: TokenKind::PipeForward,
+                        literal: "|>".to_owned(),
+                        position,
                     }
                 } else {
                     Token {
-                        kind: TokenKind::Illegal,
-                        literal: self.ch.to_string(),
+                        kind: TokenKind::Pipe,
+                        literal: "|".to_owned(),
+                        position,
                     }
                 }
             }
-            '+' => Token {
-                kind: TokenKind::Plus,
-                literal: "+".to_owned(),
-            },
-            '-' => Token {
-                kind: TokenKind::Minus,
-                literal: "-".to_owned(),
-            },
-            '/' => Token {
-                kind: TokenKind::Slash,
-                literal: "/".to_owned(),
+            '^' => Token {
+                kind: TokenKind::Caret,
+                literal: "^".to_owned(),
+                position,
             },
-            '*' => Token {
-                kind: TokenKind::Asterisk,
-                literal: "*".to_owned(),
-            },
-            '%' => Token {
-                kind: TokenKind::Percentage,
-                literal: "%".to_owned(),
+            '~' => Token {
+                kind: TokenKind::Tilde,
+                literal: "~".to_owned(),
+                position,
             },
+            '+' => {
+                if self.peek_char() == '=' {
+                    self.eat_char();
+                    Token {
+                        kind: TokenKind::PlusAssign,
+                        literal: "+=".to_owned(),
+                        position,
+                    }
+                } else {
+                    Token {
+                        kind: TokenKind::Plus,
+                        literal: "+".to_owned(),
+                        position,
+                    }
+                }
+            }
+            '-' => {
+                if self.peek_char() == '=' {
+                    self.eat_char();
+                    Token {
+                        kind: TokenKind::MinusAssign,
+                        literal: "-=".to_owned(),
+                        position,
+                    }
+                } else {
+                    Token {
+                        kind: TokenKind::Minus,
+                        literal: "-".to_owned(),
+                        position,
+                    }
+                }
+            }
+            '/' => {
+                if self.peek_char() == '=' {
+                    self.eat_char();
+                    Token {
+                        kind: TokenKind::SlashAssign,
+                        literal: "/=".to_owned(),
+                        position,
+                    }
+                } else {
+                    Token {
+                        kind: TokenKind::Slash,
+                        literal: "/".to_owned(),
+                        position,
+                    }
+                }
+            }
+            '*' => {
+                if self.peek_char() == '=' {
+                    self.eat_char();
+                    Token {
+                        kind: TokenKind::AsteriskAssign,
+                        literal: "*=".to_owned(),
+                        position,
+                    }
+                } else if self.peek_char() == '*' {
+                    self.eat_char();
+                    Token {
+                        kind: TokenKind::Power,
+                        literal: "**".to_owned(),
+                        position,
+                    }
+                } else {
+                    Token {
+                        kind: TokenKind::Asterisk,
+                        literal: "*".to_owned(),
+                        position,
+                    }
+                }
+            }
+            '%' => {
+                if self.peek_char() == '=' {
+                    self.eat_char();
+                    Token {
+                        kind: TokenKind::PercentageAssign,
+                        literal: "%=".to_owned(),
+                        position,
+                    }
+                } else {
+                    Token {
+                        kind: TokenKind::Percentage,
+                        literal: "%".to_owned(),
+                        position,
+                    }
+                }
+            }
             '(' => Token {
                 kind: TokenKind::LeftParen,
                 literal: "(".to_owned(),
+                position,
             },
             ')' => Token {
                 kind: TokenKind::RightParen,
                 literal: ")".to_owned(),
+                position,
             },
             '{' => Token {
                 kind: TokenKind::LeftBrace,
                 literal: "{".to_owned(),
+                position,
             },
             '}' => Token {
                 kind: TokenKind::RightBrace,
                 literal: "}".to_owned(),
+                position,
             },
             '[' => Token {
                 kind: TokenKind::LeftSquare,
                 literal: "[".to_owned(),
+                position,
             },
             ']' => Token {
                 kind: TokenKind::RightSquare,
                 literal: "]".to_owned(),
+                position,
             },
             ':' => Token {
                 kind: TokenKind::Colon,
                 literal: ":".to_owned(),
+                position,
+            },
+            '?' => Token {
+                kind: TokenKind::Question,
+                literal: "?".to_owned(),
+                position,
+            },
+            '.' => Token {
+                kind: TokenKind::Dot,
+                literal: ".".to_owned(),
+                position,
             },
             ';' => Token {
                 kind: TokenKind::Semicolon,
                 literal: ";".to_owned(),
+                position,
             },
             ',' => Token {
                 kind: TokenKind::Comma,
                 literal: ",".to_owned(),
+                position,
             },
-            '"' => {
-                let literal = self.eat_string().to_owned();
+            'r' if self.peek_nth_char(0) == '"' => {
+                self.eat_char();
 
                 Token {
                     kind: TokenKind::String,
-                    literal,
+                    literal: self.eat_raw_string(),
+                    position,
+                }
+            }
+            '"' => {
+                let multiline = self.peek_char() == '"' && self.peek_nth_char(1) == '"';
+                if multiline {
+                    self.eat_char();
+                    self.eat_char();
+                }
+
+                match self.eat_string(multiline) {
+                    Ok(literal) => Token {
+                        kind: TokenKind::String,
+                        literal,
+                        position,
+                    },
+                    // the literal holds the invalid escape sequence
+                    Err(literal) => Token {
+                        kind: TokenKind::Illegal,
+                        literal,
+                        position,
+                    },
                 }
             }
             EOF_CHAR => Token {
                 kind: TokenKind::Eof,
                 literal: "".to_owned(),
+                position,
             },
             _ => {
                 if self.ch.is_alphabetic() || self.ch == '_' {
@@ -251,6 +545,7 @@ impl<'a> Lexer<'a> {
                     return Token {
                         kind,
                         literal: literal.to_owned(),
+                        position,
                     };
                 } else if self.ch.is_ascii_digit() {
                     let literal = self.eat_number().to_owned();
@@ -258,11 +553,13 @@ impl<'a> Lexer<'a> {
                     return Token {
                         kind: TokenKind::Integer,
                         literal,
+                        position,
                     };
                 } else {
                     Token {
                         kind: TokenKind::Illegal,
                         literal: self.ch.to_string(),
+                        position,
                     }
                 }
             }
@@ -399,4 +696,324 @@ mod tests {
 
         test_tokenization_iter(input, tests)
     }
+
+    #[test]
+    fn next_token_string_escapes() {
+        let input = r#"
+            "\x41\x42";
+            "a\tb\n\"c\"\\";
+            "\u{1F600}";
+            "\xZZ";
+            "\x80" "\x4" "\u{110000}" "\q" "end"
+        "#;
+
+        let tests = vec![
+            (TokenKind::String, "AB"),
+            (TokenKind::Semicolon, ";"),
+            (TokenKind::String, "a\tb\n\"c\"\\"),
+            (TokenKind::Semicolon, ";"),
+            (TokenKind::String, "\u{1F600}"),
+            (TokenKind::Semicolon, ";"),
+            (TokenKind::Illegal, "\\x"),
+            (TokenKind::Semicolon, ";"),
+            (TokenKind::Illegal, "\\x80"),
+            (TokenKind::Illegal, "\\x4"),
+            (TokenKind::Illegal, "\\u{110000}"),
+            (TokenKind::Illegal, "\\q"),
+            (TokenKind::String, "end"),
+            (TokenKind::Eof, ""),
+        ];
+
+        test_tokenization_iter(input, tests)
+    }
+
+    #[test]
+    fn next_token_raw_and_multiline_strings() {
+        let input = "
+            r\"C:\\path\\n\";
+            \"\"\"first \"line\"
+second\\t\"\"\";
+            \"\" r \"\"\"\"\"\" \"end\"
+        ";
+
+        let tests = vec![
+            (TokenKind::String, "C:\\path\\n"),
+            (TokenKind::Semicolon, ";"),
+            (TokenKind::String, "first \"line\"\nsecond\t"),
+            (TokenKind::Semicolon, ";"),
+            (TokenKind::String, ""),
+            (TokenKind::Identifier, "r"),
+            (TokenKind::String, ""),
+            (TokenKind::String, "end"),
+            (TokenKind::Eof, ""),
+        ];
+
+        test_tokenization_iter(input, tests)
+    }
+
+    #[test]
+    fn next_token_non_ascii() {
+        let input = r#"let s = "é日本"; let abc = 5; s + "…" + été"#;
+
+        let tests = vec![
+            (TokenKind::Let, "let"),
+            (TokenKind::Identifier, "s"),
+            (TokenKind::Assign, "="),
+            (TokenKind::String, "é日本"),
+            (TokenKind::Semicolon, ";"),
+            (TokenKind::Let, "let"),
+            (TokenKind::Identifier, "abc"),
+            (TokenKind::Assign, "="),
+            (TokenKind::Integer, "5"),
+            (TokenKind::Semicolon, ";"),
+            (TokenKind::Identifier, "s"),
+            (TokenKind::Plus, "+"),
+            (TokenKind::String, "…"),
+            (TokenKind::Plus, "+"),
+            (TokenKind::Identifier, "été"),
+            (TokenKind::Eof, ""),
+        ];
+
+        test_tokenization_iter(input, tests)
+    }
+
+    #[test]
+    fn next_token_logical_operators() {
+        let input = "true && false || true & |";
+
+        let tests = vec![
+            (TokenKind::True, "true"),
+            (TokenKind::AndAnd, "&&"),
+            (TokenKind::False, "false"),
+            (TokenKind::OrOr, "||"),
+            (TokenKind::True, "true"),
+            (TokenKind::Ampersand, "&"),
+            (TokenKind::Pipe, "|"),
+            (TokenKind::Eof, ""),
+        ];
+
+        test_tokenization_iter(input, tests)
+    }
+
+    #[test]
+    fn next_token_comments() {
+        let input =
+            "// leading\nlet a = 1 / /* inline */ 2; // trailing\n/* multi\nline */ a /= 3;//";
+
+        let tests = vec![
+            (TokenKind::Let, "let"),
+            (TokenKind::Identifier, "a"),
+            (TokenKind::Assign, "="),
+            (TokenKind::Integer, "1"),
+            (TokenKind::Slash, "/"),
+            (TokenKind::Integer, "2"),
+            (TokenKind::Semicolon, ";"),
+            (TokenKind::Identifier, "a"),
+            (TokenKind::SlashAssign, "/="),
+            (TokenKind::Integer, "3"),
+            (TokenKind::Semicolon, ";"),
+            (TokenKind::Eof, ""),
+        ];
+
+        test_tokenization_iter(input, tests);
+
+        let mut lexer = Lexer::new("1 /* never closed");
+        lexer.next_token();
+        let tok = lexer.next_token();
+        assert_eq!(tok.kind, TokenKind::Illegal);
+        assert_eq!(tok.position, Position { line: 1, column: 3 });
+        assert_eq!(lexer.next_token().kind, TokenKind::Eof);
+    }
+
+    #[test]
+    fn next_token_method_call() {
+        let input = "arr.map(f).len()";
+
+        let tests = vec![
+            (TokenKind::Identifier, "arr"),
+            (TokenKind::Dot, "."),
+            (TokenKind::Identifier, "map"),
+            (TokenKind::LeftParen, "("),
+            (TokenKind::Identifier, "f"),
+            (TokenKind::RightParen, ")"),
+            (TokenKind::Dot, "."),
+            (TokenKind::Identifier, "len"),
+            (TokenKind::LeftParen, "("),
+            (TokenKind::RightParen, ")"),
+            (TokenKind::Eof, ""),
+        ];
+
+        test_tokenization_iter(input, tests)
+    }
+
+    #[test]
+    fn next_token_match() {
+        let input = r#"match x { 1 => "one", _ => "other" }"#;
+
+        let tests = vec![
+            (TokenKind::Match, "match"),
+            (TokenKind::Identifier, "x"),
+            (TokenKind::LeftBrace, "{"),
+            (TokenKind::Integer, "1"),
+            (TokenKind::FatArrow, "=>"),
+            (TokenKind::String, "one"),
+            (TokenKind::Comma, ","),
+            (TokenKind::Identifier, "_"),
+            (TokenKind::FatArrow, "=>"),
+            (TokenKind::String, "other"),
+            (TokenKind::RightBrace, "}"),
+            (TokenKind::Eof, ""),
+        ];
+
+        test_tokenization_iter(input, tests)
+    }
+
+    #[test]
+    fn next_token_pipe_forward() {
+        let input = "x |> f | g || h";
+
+        let tests = vec![
+            (TokenKind::Identifier, "x"),
+            (TokenKind::PipeForward, "|>"),
+            (TokenKind::Identifier, "f"),
+            (TokenKind::Pipe, "|"),
+            (TokenKind::Identifier, "g"),
+            (TokenKind::OrOr, "||"),
+            (TokenKind::Identifier, "h"),
+            (TokenKind::Eof, ""),
+        ];
+
+        test_tokenization_iter(input, tests)
+    }
+
+    #[test]
+    fn next_token_ternary() {
+        let input = "a ? 1 : 2";
+
+        let tests = vec![
+            (TokenKind::Identifier, "a"),
+            (TokenKind::Question, "?"),
+            (TokenKind::Integer, "1"),
+            (TokenKind::Colon, ":"),
+            (TokenKind::Integer, "2"),
+            (TokenKind::Eof, ""),
+        ];
+
+        test_tokenization_iter(input, tests)
+    }
+
+    #[test]
+    fn next_token_bitwise_operators() {
+        let input = "6 & 3 | ~x ^ 1";
+
+        let tests = vec![
+            (TokenKind::Integer, "6"),
+            (TokenKind::Ampersand, "&"),
+            (TokenKind::Integer, "3"),
+            (TokenKind::Pipe, "|"),
+            (TokenKind::Tilde, "~"),
+            (TokenKind::Identifier, "x"),
+            (TokenKind::Caret, "^"),
+            (TokenKind::Integer, "1"),
+            (TokenKind::Eof, ""),
+        ];
+
+        test_tokenization_iter(input, tests)
+    }
+
+    #[test]
+    fn next_token_shift_operators() {
+        let input = "1 << 4 >> 2 < <= > >=";
+
+        let tests = vec![
+            (TokenKind::Integer, "1"),
+            (TokenKind::ShiftLeft, "<<"),
+            (TokenKind::Integer, "4"),
+            (TokenKind::ShiftRight, ">>"),
+            (TokenKind::Integer, "2"),
+            (TokenKind::LessThan, "<"),
+            (TokenKind::LessThanEqual, "<="),
+            (TokenKind::GreaterThan, ">"),
+            (TokenKind::GreaterThanEqual, ">="),
+            (TokenKind::Eof, ""),
+        ];
+
+        test_tokenization_iter(input, tests)
+    }
+
+    #[test]
+    fn next_token_power() {
+        let input = "2 ** 3 * 4 *= 5";
+
+        let tests = vec![
+            (TokenKind::Integer, "2"),
+            (TokenKind::Power, "**"),
+            (TokenKind::Integer, "3"),
+            (TokenKind::Asterisk, "*"),
+            (TokenKind::Integer, "4"),
+            (TokenKind::AsteriskAssign, "*="),
+            (TokenKind::Integer, "5"),
+            (TokenKind::Eof, ""),
+        ];
+
+        test_tokenization_iter(input, tests)
+    }
+
+    #[test]
+    fn next_token_nil() {
+        let input = "nil == nils";
+
+        let tests = vec![
+            (TokenKind::Nil, "nil"),
+            (TokenKind::Equal, "=="),
+            (TokenKind::Identifier, "nils"),
+            (TokenKind::Eof, ""),
+        ];
+
+        test_tokenization_iter(input, tests)
+    }
+
+    #[test]
+    fn next_token_compound_assign() {
+        let input = "+= -= *= /= %= + =";
+
+        let tests = vec![
+            (TokenKind::PlusAssign, "+="),
+            (TokenKind::MinusAssign, "-="),
+            (TokenKind::AsteriskAssign, "*="),
+            (TokenKind::SlashAssign, "/="),
+            (TokenKind::PercentageAssign, "%="),
+            (TokenKind::Plus, "+"),
+            (TokenKind::Assign, "="),
+            (TokenKind::Eof, ""),
+        ];
+
+        test_tokenization_iter(input, tests)
+    }
+
+    #[test]
+    fn next_token_position() {
+        let input = "let a = 1;\n  a + 2";
+        let mut lexer = Lexer::new(input);
+
+        let positions = std::iter::from_fn(|| {
+            let tok = lexer.next_token();
+            (tok.kind != TokenKind::Eof).then_some((tok.position.line, tok.position.column))
+        })
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            positions,
+            vec![
+                (1, 1),
+                (1, 5),
+                (1, 7),
+                (1, 9),
+                (1, 10),
+                (2, 3),
+                (2, 5),
+                (2, 7)
+            ]
+        );
+    }
 }
@@ -1,7 +1,56 @@
+pub mod analyzer;
 pub mod ast;
 pub mod environment;
 pub mod evaluator;
 pub mod lexer;
 pub mod object;
 pub mod parser;
+pub mod time;
 pub mod token;
+
+use evaluator::Evaluator;
+use object::{EvalError, Object};
+
+/// Version of the interpreter, as published in the crate manifest.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Runs a program with the default settings and returns the value of its last statement,
+/// or `Object::UnitValue` for an empty program.
+///
+/// ```
+/// use qalo::object::Object;
+///
+/// assert_eq!(qalo::eval("let x = 2; x * 3").unwrap(), Object::IntegerValue(6));
+/// assert_eq!(qalo::eval("").unwrap(), Object::UnitValue);
+/// ```
+///
+/// Errors are returned as is, e.g. to be rendered with `Evaluator::render_error`:
+///
+/// ```
+/// assert!(qalo::eval("1 + true").is_err());
+/// ```
+///
+/// Use an `Evaluator` to change the settings, e.g. the recursion limit or the deadline.
+pub fn eval(source: &str) -> Result<Object, EvalError> {
+    let mut evaluator = Evaluator::new(source);
+    let mut objects = evaluator.eval_program()?;
+
+    Ok(objects.pop().unwrap_or(Object::UnitValue))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eval_source() {
+        assert_eq!(eval("1 + 2").unwrap(), Object::IntegerValue(3));
+        assert_eq!(eval("1; 2;").unwrap(), Object::IntegerValue(2));
+        assert_eq!(eval("").unwrap(), Object::UnitValue);
+        assert_eq!(eval("let x = 1;").unwrap(), Object::UnitValue);
+        assert!(matches!(
+            eval("missing"),
+            Err(EvalError::IdentifierNotFound(_))
+        ));
+    }
+}
@@ -1,22 +1,208 @@
-use std::{env, error::Error, fs, process};
+use std::{
+    env,
+    error::Error,
+    fs,
+    io::{self, BufRead, Write},
+    process, thread,
+};
 
-use qalo::evaluator::Evaluator;
+use qalo::{
+    analyzer::Analyzer, evaluator::Evaluator, lexer::Lexer, object::Object, token::TokenKind,
+    VERSION,
+};
+
+/// Stack size of the thread running the programs: deeply recursive programs need
+/// more than the main thread's stack to reach `MAX_DEPTH` before overflowing the
+/// native stack, especially in debug builds.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// Recursion limit of the programs, higher than the evaluator's default since they run
+/// on a thread with `STACK_SIZE`.
+const MAX_DEPTH: usize = 1000;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let files = env::args()
-        .into_iter()
-        .filter(|file| file.ends_with(".ql"))
-        .collect::<Vec<String>>();
+    if env::args().any(|arg| arg == "--version") {
+        println!("qalo {VERSION}");
+        return Ok(());
+    }
+
+    // the first argument is the path of the executable itself
+    let files = env::args().skip(1).collect::<Vec<String>>();
+
+    let unsupported: Vec<&String> = files.iter().filter(|file| !file.ends_with(".ql")).collect();
+    if !unsupported.is_empty() {
+        for file in unsupported {
+            eprintln!("Only .ql files can be run, got `{file}`");
+        }
+        process::exit(1);
+    }
+
+    let runner = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || run(files))?;
+
+    if runner.join().is_err() {
+        process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn run(files: Vec<String>) {
+    if files.is_empty() {
+        return repl();
+    }
 
     for file in files {
         let source = fs::read_to_string(file).expect("Failed to read a file");
 
+        // syntax errors are reported by the evaluator, only warnings are of interest here
+        if let Ok(warnings) = Analyzer::new(&source).analyze_program() {
+            for warning in warnings {
+                eprintln!("| Qalo Warning |\n{warning}");
+            }
+        }
+
         let mut evaluator = Evaluator::new(&source);
+        evaluator.set_max_depth(MAX_DEPTH);
         evaluator.eval_program().unwrap_or_else(|err| {
-            eprintln!("| Qalo Error |\n{err}");
+            eprintln!("{}", evaluator.render_error(&err));
             process::exit(1);
         });
     }
+}
 
-    Ok(())
+/// Reads programs from the standard input until its end, evaluating them in the same environment.
+fn repl() {
+    println!("qalo {VERSION}");
+
+    let mut session = Session::new();
+    let mut lines = io::stdin().lock().lines();
+
+    loop {
+        print!("{}", if session.is_pending() { ".. " } else { ">> " });
+        io::stdout().flush().expect("Failed to write the prompt");
+
+        let Some(Ok(line)) = lines.next() else {
+            println!();
+            return;
+        };
+
+        match session.handle_line(&line) {
+            Some(Ok(Object::UnitValue)) | None => {}
+            Some(Ok(object)) => println!("{object}"),
+            Some(Err(err)) => eprintln!("{err}"),
+        }
+    }
+}
+
+/// State of a REPL, which keeps the bindings made by the previous inputs.
+struct Session {
+    evaluator: Evaluator<'static>,
+    /// Lines read so far for an input that isn't complete yet, e.g. a function body.
+    pending: String,
+}
+
+impl Session {
+    fn new() -> Self {
+        let mut evaluator = Evaluator::new("");
+        evaluator.set_max_depth(MAX_DEPTH);
+
+        Session {
+            evaluator,
+            pending: String::new(),
+        }
+    }
+
+    fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Adds a line to the current input and evaluates it once it's complete, giving back
+    /// the value of its last statement or the rendered error. Gives back `None` while
+    /// more lines are needed.
+    fn handle_line(&mut self, line: &str) -> Option<Result<Object, String>> {
+        self.pending.push_str(line);
+        self.pending.push('\n');
+
+        if !is_complete(&self.pending) {
+            return None;
+        }
+
+        let source = std::mem::take(&mut self.pending);
+        if source.trim().is_empty() {
+            return None;
+        }
+
+        Some(
+            self.evaluator
+                .eval_str(&source)
+                .map(|mut objects| objects.pop().unwrap_or(Object::UnitValue))
+                .map_err(|err| self.evaluator.render_error(&err)),
+        )
+    }
+}
+
+/// Whether every brace, parenthesis and square bracket opened by `source` is closed.
+/// Extra closing ones make it complete, so that the evaluator reports them.
+fn is_complete(source: &str) -> bool {
+    let mut lexer = Lexer::new(source);
+    let mut depth = 0;
+
+    loop {
+        match lexer.next_token().kind {
+            TokenKind::LeftBrace | TokenKind::LeftParen | TokenKind::LeftSquare => depth += 1,
+            TokenKind::RightBrace | TokenKind::RightParen | TokenKind::RightSquare => depth -= 1,
+            TokenKind::Eof => return depth <= 0,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repl_lines() {
+        let mut session = Session::new();
+        assert_eq!(
+            session.handle_line("let x = 1;"),
+            Some(Ok(Object::UnitValue))
+        );
+        assert_eq!(
+            session.handle_line("x + 1"),
+            Some(Ok(Object::IntegerValue(2)))
+        );
+        assert_eq!(session.handle_line(""), None);
+
+        // errors are rendered, and the session goes on
+        assert!(matches!(session.handle_line("missing"), Some(Err(_))));
+        assert!(matches!(session.handle_line("let = 1;"), Some(Err(_))));
+        assert_eq!(session.handle_line("x"), Some(Ok(Object::IntegerValue(1))));
+
+        // lines are accumulated while brackets aren't balanced
+        assert_eq!(session.handle_line("fn add(a, b) {"), None);
+        assert!(session.is_pending());
+        assert_eq!(session.handle_line("  a + b"), None);
+        assert_eq!(session.handle_line("}"), Some(Ok(Object::UnitValue)));
+        assert!(!session.is_pending());
+        assert_eq!(session.handle_line("add(x, ["), None);
+        assert_eq!(
+            session.handle_line("2][0])"),
+            Some(Ok(Object::IntegerValue(3)))
+        );
+    }
+
+    #[test]
+    fn repl_complete_inputs() {
+        assert!(is_complete("let x = 1;"));
+        assert!(is_complete("fn f() { [1, (2)] }"));
+        assert!(is_complete("}"));
+        assert!(is_complete(r#""{" + "(""#));
+        assert!(is_complete("// {"));
+        assert!(!is_complete("if x {"));
+        assert!(!is_complete("f(1,"));
+        assert!(!is_complete("{ /* } */"));
+    }
 }
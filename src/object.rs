@@ -1,10 +1,17 @@
-use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    fmt,
+    rc::Rc,
+    string::FromUtf8Error,
+};
 
 use thiserror::Error;
 
 use crate::{
     ast::{ParserError, Statement},
     environment::Environment,
+    time::{format_timestamp, DEFAULT_FORMAT},
     token::TokenKind,
 };
 
@@ -14,13 +21,121 @@ pub enum Object {
     BooleanValue(bool),
     StringValue(String),
     ArrayValue(Vec<Object>),
-    MapValue(HashMap<String, Object>),
+    /// Sorted by key, so that maps are displayed and iterated in a predictable order.
+    MapValue(BTreeMap<HashableObject, Object>),
+    BytesValue(Vec<u8>),
+    /// A point in time, as the number of seconds since the Unix epoch.
+    TimeValue(i64),
     ReturnValue(Box<Object>),
-    FunctionValue(Closure),
+    BreakValue,
+    ContinueValue,
+    FunctionValue(FunctionRef),
     BuiltinValue(BuiltinFunction),
+    MemoizedValue(Memoized),
+    NativeValue(NativeFunction),
+    /// A function bound through `Evaluator::redefine`, shared by all its references.
+    RedefinableValue(FunctionCell),
+    /// The value of `nil`, written explicitly to mark a missing value.
+    /// Unlike `UnitValue`, it's never produced implicitly (e.g. by a function without a result).
+    NilValue,
     UnitValue,
 }
 
+impl Object {
+    /// Whether this object can be called like a function.
+    pub fn is_callable(&self) -> bool {
+        matches!(
+            self,
+            Object::FunctionValue(_)
+                | Object::BuiltinValue(_)
+                | Object::MemoizedValue(_)
+                | Object::NativeValue(_)
+                | Object::RedefinableValue(_)
+        )
+    }
+
+    /// Name of the runtime type of this object, e.g. `"integer"`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::IntegerValue(_) => "integer",
+            Object::BooleanValue(_) => "boolean",
+            Object::StringValue(_) => "string",
+            Object::ArrayValue(_) => "array",
+            Object::MapValue(_) => "map",
+            Object::BytesValue(_) => "bytes",
+            Object::TimeValue(_) => "time",
+            Object::FunctionValue(_)
+            | Object::BuiltinValue(_)
+            | Object::MemoizedValue(_)
+            | Object::NativeValue(_)
+            | Object::RedefinableValue(_) => "function",
+            Object::NilValue => "nil",
+            Object::UnitValue => "unit",
+            // control flow values never reach programs, they're only named for completeness
+            Object::ReturnValue(value) => value.type_name(),
+            Object::BreakValue => "break",
+            Object::ContinueValue => "continue",
+        }
+    }
+
+    /// Whether this object counts as true in conditions when truthiness is enabled:
+    /// `false`, `0`, `nil`, `()` and empty strings, arrays, maps and bytes are falsy.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Object::BooleanValue(value) => *value,
+            Object::IntegerValue(value) => *value != 0,
+            Object::StringValue(value) => !value.is_empty(),
+            Object::ArrayValue(elements) => !elements.is_empty(),
+            Object::MapValue(map) => !map.is_empty(),
+            Object::BytesValue(bytes) => !bytes.is_empty(),
+            Object::NilValue | Object::UnitValue => false,
+            Object::ReturnValue(value) => value.is_truthy(),
+            _ => true,
+        }
+    }
+
+    /// Map tagged as an optional or a result, e.g. `{"tag": "Some", "value": 3}`.
+    /// `None` is the only tag without a value.
+    pub fn tagged(tag: &str, value: Option<Object>) -> Object {
+        let mut map = BTreeMap::from([(
+            HashableObject::String("tag".to_owned()),
+            Object::StringValue(tag.to_owned()),
+        )]);
+        if let Some(value) = value {
+            map.insert(HashableObject::String("value".to_owned()), value);
+        }
+
+        Object::MapValue(map)
+    }
+
+    /// Tag and value of the maps built by `Some`, `None`, `Ok` and `Err`.
+    pub fn as_tagged(&self) -> Option<(&str, Option<&Object>)> {
+        let Object::MapValue(map) = self else {
+            return None;
+        };
+        let Some(Object::StringValue(tag)) = map.get(&HashableObject::String("tag".to_owned()))
+        else {
+            return None;
+        };
+        let value = map.get(&HashableObject::String("value".to_owned()));
+
+        match (tag.as_str(), value) {
+            ("None", None) if map.len() == 1 => Some(("None", None)),
+            ("Some" | "Ok" | "Err", Some(_)) if map.len() == 2 => Some((tag, value)),
+            _ => None,
+        }
+    }
+
+    /// Stringifies this object like `Display` does, except that strings aren't quoted.
+    /// Strings nested in other values (e.g. arrays) are still quoted.
+    pub fn to_raw_string(&self) -> String {
+        match self {
+            Object::StringValue(value) => value.clone(),
+            other => other.to_string(),
+        }
+    }
+}
+
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -43,31 +158,162 @@ impl fmt::Display for Object {
                     if i > 0 {
                         write!(f, ", ")?;
                     }
-                    write!(f, "\"{key}\": {value}")?;
+                    write!(f, "{key}: {value}")?;
                 }
                 write!(f, "}}")
             }
-            Object::FunctionValue(value) => write!(f, "{value}"),
+            Object::BytesValue(bytes) => write!(f, "b\"{}\"", bytes.escape_ascii()),
+            Object::TimeValue(timestamp) => {
+                write!(f, "{}", format_timestamp(*timestamp, DEFAULT_FORMAT))
+            }
+            Object::FunctionValue(FunctionRef(value)) => write!(f, "{value}"),
             Object::ReturnValue(value) => write!(f, "return {value}"),
+            Object::BreakValue => write!(f, "break"),
+            Object::ContinueValue => write!(f, "continue"),
             Object::BuiltinValue(value) => write!(f, "built-in function {value}"),
+            Object::MemoizedValue(value) => write!(f, "memoized {}", value.function),
+            Object::NativeValue(value) => write!(f, "native function {}", value.name),
+            Object::RedefinableValue(value) => write!(f, "{}", value.function.borrow()),
+            Object::NilValue => write!(f, "nil"),
             Object::UnitValue => write!(f, "()"),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Clone)]
 pub struct Closure {
     pub parameters: Vec<String>,
-    pub body: Statement,
+    pub body: Box<Statement>,
     pub env: Rc<RefCell<Environment>>,
 }
 
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // only the names bound by the environment are shown: it usually contains the closure itself
+        let mut names: Vec<_> = self.env.borrow().store.keys().cloned().collect();
+        names.sort();
+
+        f.debug_struct("Closure")
+            .field("parameters", &self.parameters)
+            .field("body", &self.body)
+            .field("env", &names)
+            .finish()
+    }
+}
+
 impl fmt::Display for Closure {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "fn({}) {}", self.parameters.join(", "), self.body)
     }
 }
 
+/// A closure shared between all the copies of a function, which are equal only to each other:
+/// two definitions with the same code are still different functions, and comparing their
+/// environments could recurse forever.
+#[derive(Debug, Clone)]
+pub struct FunctionRef(pub Rc<Closure>);
+
+impl PartialEq for FunctionRef {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for FunctionRef {}
+
+/// Subset of objects that can be hashed, e.g. to be used as keys of maps and caches.
+/// Keys of different types are ordered as integers, booleans and then strings.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub enum HashableObject {
+    Integer(i32),
+    Boolean(bool),
+    String(String),
+}
+
+impl TryFrom<Object> for HashableObject {
+    /// Objects that can't be hashed are given back.
+    type Error = Object;
+
+    fn try_from(obj: Object) -> Result<Self, Self::Error> {
+        match obj {
+            Object::IntegerValue(value) => Ok(HashableObject::Integer(value)),
+            Object::BooleanValue(value) => Ok(HashableObject::Boolean(value)),
+            Object::StringValue(value) => Ok(HashableObject::String(value)),
+            other => Err(other),
+        }
+    }
+}
+
+impl fmt::Display for HashableObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HashableObject::Integer(value) => write!(f, "{value}"),
+            HashableObject::Boolean(value) => write!(f, "{value}"),
+            HashableObject::String(value) => write!(f, "\"{value}\""),
+        }
+    }
+}
+
+impl From<HashableObject> for Object {
+    fn from(obj: HashableObject) -> Self {
+        match obj {
+            HashableObject::Integer(value) => Object::IntegerValue(value),
+            HashableObject::Boolean(value) => Object::BooleanValue(value),
+            HashableObject::String(value) => Object::StringValue(value),
+        }
+    }
+}
+
+/// A function whose results are cached by its (hashable) arguments.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Memoized {
+    pub function: Box<Object>,
+    pub cache: Rc<RefCell<HashMap<Vec<HashableObject>, Object>>>,
+}
+
+/// Signature of the functions that embedders register into the evaluator.
+pub type NativeFn = dyn Fn(Vec<Object>) -> Result<Object, EvalError>;
+
+/// A function implemented by the program embedding the interpreter.
+#[derive(Clone)]
+pub struct NativeFunction {
+    pub name: String,
+    pub function: Rc<NativeFn>,
+}
+
+impl fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NativeFunction")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && Rc::ptr_eq(&self.function, &other.function)
+    }
+}
+
+impl Eq for NativeFunction {}
+
+/// A named function whose definition can be replaced, e.g. from a REPL: since the cell is
+/// shared, aliases and closures referring to it always call the latest definition.
+#[derive(Debug, Clone)]
+pub struct FunctionCell {
+    pub name: String,
+    pub function: Rc<RefCell<Object>>,
+}
+
+impl PartialEq for FunctionCell {
+    fn eq(&self, other: &Self) -> bool {
+        // the same cell stays equal to itself across redefinitions
+        Rc::ptr_eq(&self.function, &other.function)
+    }
+}
+
+impl Eq for FunctionCell {}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum BuiltinFunction {
     Len,
@@ -75,9 +321,142 @@ pub enum BuiltinFunction {
     Rest,
     Println,
     Print,
+    Bytes,
+    FromUtf8,
+    Template,
+    Memoize,
+    Gcd,
+    Lcm,
+    MergeWith,
+    IndexOf,
+    LastIndexOf,
+    Cfg,
+    Matches,
+    First,
+    Last,
+    Map,
+    GroupBy,
+    Filter,
+    Version,
+    Reduce,
+    MapValues,
+    MapKeys,
+    Keys,
+    Values,
+    Contains,
+    CharAt,
+    Delete,
+    Split,
+    NowSeconds,
+    Time,
+    FormatTime,
+    Join,
+    Str,
+    Int,
+    SortBy,
+    Type,
+    Range,
+    Abs,
+    Min,
+    Max,
+    Pick,
+    Omit,
+    FormatNumber,
+    IsBuiltin,
+    Window,
+    Get,
+    Base64Encode,
+    Base64Decode,
+    Some,
+    Ok,
+    Err,
+    IsSome,
+    IsNone,
+    Unwrap,
+    UnwrapOr,
+    Partition,
+    Repeat,
+    Invert,
+    Fit,
+    Debug,
+    Scan,
+    Eprintln,
+    Eprint,
 }
 
 impl BuiltinFunction {
+    /// Names of all built-ins, which must be kept in sync with `lookup_function`.
+    /// They're all functions, except for the `None` value.
+    pub const NAMES: &'static [&'static str] = &[
+        "len",
+        "append",
+        "rest",
+        "println",
+        "print",
+        "bytes",
+        "from_utf8",
+        "template",
+        "memoize",
+        "gcd",
+        "lcm",
+        "merge_with",
+        "index_of",
+        "last_index_of",
+        "cfg",
+        "matches",
+        "first",
+        "last",
+        "map",
+        "group_by",
+        "filter",
+        "version",
+        "reduce",
+        "map_values",
+        "map_keys",
+        "keys",
+        "values",
+        "contains",
+        "char_at",
+        "delete",
+        "split",
+        "now_seconds",
+        "time",
+        "format_time",
+        "join",
+        "str",
+        "int",
+        "sort_by",
+        "type",
+        "range",
+        "abs",
+        "min",
+        "max",
+        "pick",
+        "omit",
+        "format_number",
+        "is_builtin",
+        "window",
+        "get",
+        "base64_encode",
+        "base64_decode",
+        "Some",
+        "None",
+        "Ok",
+        "Err",
+        "is_some",
+        "is_none",
+        "unwrap",
+        "unwrap_or",
+        "partition",
+        "repeat",
+        "invert",
+        "fit",
+        "debug",
+        "scan",
+        "eprintln",
+        "eprint",
+    ];
+
     /// Matches built-in functions.
     pub fn lookup_function(identifier: &str) -> Result<Object, EvalError> {
         match identifier {
@@ -86,6 +465,69 @@ impl BuiltinFunction {
             "rest" => Ok(Object::BuiltinValue(BuiltinFunction::Rest)),
             "println" => Ok(Object::BuiltinValue(BuiltinFunction::Println)),
             "print" => Ok(Object::BuiltinValue(BuiltinFunction::Print)),
+            "bytes" => Ok(Object::BuiltinValue(BuiltinFunction::Bytes)),
+            "from_utf8" => Ok(Object::BuiltinValue(BuiltinFunction::FromUtf8)),
+            "template" => Ok(Object::BuiltinValue(BuiltinFunction::Template)),
+            "memoize" => Ok(Object::BuiltinValue(BuiltinFunction::Memoize)),
+            "gcd" => Ok(Object::BuiltinValue(BuiltinFunction::Gcd)),
+            "lcm" => Ok(Object::BuiltinValue(BuiltinFunction::Lcm)),
+            "merge_with" => Ok(Object::BuiltinValue(BuiltinFunction::MergeWith)),
+            "index_of" => Ok(Object::BuiltinValue(BuiltinFunction::IndexOf)),
+            "last_index_of" => Ok(Object::BuiltinValue(BuiltinFunction::LastIndexOf)),
+            "cfg" => Ok(Object::BuiltinValue(BuiltinFunction::Cfg)),
+            "matches" => Ok(Object::BuiltinValue(BuiltinFunction::Matches)),
+            "first" => Ok(Object::BuiltinValue(BuiltinFunction::First)),
+            "last" => Ok(Object::BuiltinValue(BuiltinFunction::Last)),
+            "map" => Ok(Object::BuiltinValue(BuiltinFunction::Map)),
+            "group_by" => Ok(Object::BuiltinValue(BuiltinFunction::GroupBy)),
+            "filter" => Ok(Object::BuiltinValue(BuiltinFunction::Filter)),
+            "version" => Ok(Object::BuiltinValue(BuiltinFunction::Version)),
+            "reduce" => Ok(Object::BuiltinValue(BuiltinFunction::Reduce)),
+            "map_values" => Ok(Object::BuiltinValue(BuiltinFunction::MapValues)),
+            "map_keys" => Ok(Object::BuiltinValue(BuiltinFunction::MapKeys)),
+            "keys" => Ok(Object::BuiltinValue(BuiltinFunction::Keys)),
+            "values" => Ok(Object::BuiltinValue(BuiltinFunction::Values)),
+            "contains" => Ok(Object::BuiltinValue(BuiltinFunction::Contains)),
+            "char_at" => Ok(Object::BuiltinValue(BuiltinFunction::CharAt)),
+            "delete" => Ok(Object::BuiltinValue(BuiltinFunction::Delete)),
+            "split" => Ok(Object::BuiltinValue(BuiltinFunction::Split)),
+            "now_seconds" => Ok(Object::BuiltinValue(BuiltinFunction::NowSeconds)),
+            "time" => Ok(Object::BuiltinValue(BuiltinFunction::Time)),
+            "format_time" => Ok(Object::BuiltinValue(BuiltinFunction::FormatTime)),
+            "join" => Ok(Object::BuiltinValue(BuiltinFunction::Join)),
+            "str" => Ok(Object::BuiltinValue(BuiltinFunction::Str)),
+            "int" => Ok(Object::BuiltinValue(BuiltinFunction::Int)),
+            "sort_by" => Ok(Object::BuiltinValue(BuiltinFunction::SortBy)),
+            "type" => Ok(Object::BuiltinValue(BuiltinFunction::Type)),
+            "range" => Ok(Object::BuiltinValue(BuiltinFunction::Range)),
+            "abs" => Ok(Object::BuiltinValue(BuiltinFunction::Abs)),
+            "min" => Ok(Object::BuiltinValue(BuiltinFunction::Min)),
+            "max" => Ok(Object::BuiltinValue(BuiltinFunction::Max)),
+            "pick" => Ok(Object::BuiltinValue(BuiltinFunction::Pick)),
+            "omit" => Ok(Object::BuiltinValue(BuiltinFunction::Omit)),
+            "format_number" => Ok(Object::BuiltinValue(BuiltinFunction::FormatNumber)),
+            "is_builtin" => Ok(Object::BuiltinValue(BuiltinFunction::IsBuiltin)),
+            "window" => Ok(Object::BuiltinValue(BuiltinFunction::Window)),
+            "get" => Ok(Object::BuiltinValue(BuiltinFunction::Get)),
+            "base64_encode" => Ok(Object::BuiltinValue(BuiltinFunction::Base64Encode)),
+            "base64_decode" => Ok(Object::BuiltinValue(BuiltinFunction::Base64Decode)),
+            "Some" => Ok(Object::BuiltinValue(BuiltinFunction::Some)),
+            // the empty optional is a value rather than a constructor
+            "None" => Ok(Object::tagged("None", None)),
+            "Ok" => Ok(Object::BuiltinValue(BuiltinFunction::Ok)),
+            "Err" => Ok(Object::BuiltinValue(BuiltinFunction::Err)),
+            "is_some" => Ok(Object::BuiltinValue(BuiltinFunction::IsSome)),
+            "is_none" => Ok(Object::BuiltinValue(BuiltinFunction::IsNone)),
+            "unwrap" => Ok(Object::BuiltinValue(BuiltinFunction::Unwrap)),
+            "unwrap_or" => Ok(Object::BuiltinValue(BuiltinFunction::UnwrapOr)),
+            "partition" => Ok(Object::BuiltinValue(BuiltinFunction::Partition)),
+            "repeat" => Ok(Object::BuiltinValue(BuiltinFunction::Repeat)),
+            "invert" => Ok(Object::BuiltinValue(BuiltinFunction::Invert)),
+            "fit" => Ok(Object::BuiltinValue(BuiltinFunction::Fit)),
+            "debug" => Ok(Object::BuiltinValue(BuiltinFunction::Debug)),
+            "scan" => Ok(Object::BuiltinValue(BuiltinFunction::Scan)),
+            "eprintln" => Ok(Object::BuiltinValue(BuiltinFunction::Eprintln)),
+            "eprint" => Ok(Object::BuiltinValue(BuiltinFunction::Eprint)),
             _ => Err(EvalError::IdentifierNotFound(identifier.to_owned())),
         }
     }
@@ -99,6 +541,67 @@ impl fmt::Display for BuiltinFunction {
             BuiltinFunction::Rest => write!(f, "rest"),
             BuiltinFunction::Println => write!(f, "println"),
             BuiltinFunction::Print => write!(f, "print"),
+            BuiltinFunction::Bytes => write!(f, "bytes"),
+            BuiltinFunction::FromUtf8 => write!(f, "from_utf8"),
+            BuiltinFunction::Template => write!(f, "template"),
+            BuiltinFunction::Memoize => write!(f, "memoize"),
+            BuiltinFunction::Gcd => write!(f, "gcd"),
+            BuiltinFunction::Lcm => write!(f, "lcm"),
+            BuiltinFunction::MergeWith => write!(f, "merge_with"),
+            BuiltinFunction::IndexOf => write!(f, "index_of"),
+            BuiltinFunction::LastIndexOf => write!(f, "last_index_of"),
+            BuiltinFunction::Cfg => write!(f, "cfg"),
+            BuiltinFunction::Matches => write!(f, "matches"),
+            BuiltinFunction::First => write!(f, "first"),
+            BuiltinFunction::Last => write!(f, "last"),
+            BuiltinFunction::Map => write!(f, "map"),
+            BuiltinFunction::GroupBy => write!(f, "group_by"),
+            BuiltinFunction::Filter => write!(f, "filter"),
+            BuiltinFunction::Version => write!(f, "version"),
+            BuiltinFunction::Reduce => write!(f, "reduce"),
+            BuiltinFunction::MapValues => write!(f, "map_values"),
+            BuiltinFunction::MapKeys => write!(f, "map_keys"),
+            BuiltinFunction::Keys => write!(f, "keys"),
+            BuiltinFunction::Values => write!(f, "values"),
+            BuiltinFunction::Contains => write!(f, "contains"),
+            BuiltinFunction::CharAt => write!(f, "char_at"),
+            BuiltinFunction::Delete => write!(f, "delete"),
+            BuiltinFunction::Split => write!(f, "split"),
+            BuiltinFunction::NowSeconds => write!(f, "now_seconds"),
+            BuiltinFunction::Time => write!(f, "time"),
+            BuiltinFunction::FormatTime => write!(f, "format_time"),
+            BuiltinFunction::Join => write!(f, "join"),
+            BuiltinFunction::Str => write!(f, "str"),
+            BuiltinFunction::Int => write!(f, "int"),
+            BuiltinFunction::SortBy => write!(f, "sort_by"),
+            BuiltinFunction::Type => write!(f, "type"),
+            BuiltinFunction::Range => write!(f, "range"),
+            BuiltinFunction::Abs => write!(f, "abs"),
+            BuiltinFunction::Min => write!(f, "min"),
+            BuiltinFunction::Max => write!(f, "max"),
+            BuiltinFunction::Pick => write!(f, "pick"),
+            BuiltinFunction::Omit => write!(f, "omit"),
+            BuiltinFunction::FormatNumber => write!(f, "format_number"),
+            BuiltinFunction::IsBuiltin => write!(f, "is_builtin"),
+            BuiltinFunction::Window => write!(f, "window"),
+            BuiltinFunction::Get => write!(f, "get"),
+            BuiltinFunction::Base64Encode => write!(f, "base64_encode"),
+            BuiltinFunction::Base64Decode => write!(f, "base64_decode"),
+            BuiltinFunction::Some => write!(f, "Some"),
+            BuiltinFunction::Ok => write!(f, "Ok"),
+            BuiltinFunction::Err => write!(f, "Err"),
+            BuiltinFunction::IsSome => write!(f, "is_some"),
+            BuiltinFunction::IsNone => write!(f, "is_none"),
+            BuiltinFunction::Unwrap => write!(f, "unwrap"),
+            BuiltinFunction::UnwrapOr => write!(f, "unwrap_or"),
+            BuiltinFunction::Partition => write!(f, "partition"),
+            BuiltinFunction::Repeat => write!(f, "repeat"),
+            BuiltinFunction::Invert => write!(f, "invert"),
+            BuiltinFunction::Fit => write!(f, "fit"),
+            BuiltinFunction::Debug => write!(f, "debug"),
+            BuiltinFunction::Scan => write!(f, "scan"),
+            BuiltinFunction::Eprintln => write!(f, "eprintln"),
+            BuiltinFunction::Eprint => write!(f, "eprint"),
         }
     }
 }
@@ -117,15 +620,54 @@ pub enum EvalError {
     #[error("Division by zero isn't allowed")]
     DivisionByZero,
 
+    #[error("Negative exponents aren't allowed for integers: {0}")]
+    NegativeExponent(i32),
+
+    #[error("Integers can only be shifted by 0 to 31 bits, got {0}")]
+    InvalidShift(i32),
+
+    #[error("Integer overflow: {0}")]
+    IntegerOverflow(String),
+
     #[error("Function not found: {0}")]
     FunctionNotFound(String),
 
     #[error("Function call with the wrong number of arguments. Expected {0}, got {1}")]
     FunctionCallWrongArity(u8, u8),
 
+    #[error("Cannot assign to `{0}` because it was declared with `const`")]
+    AssignmentToImmutable(String),
+
+    #[error("Cannot bind `{0}` inside a frozen environment")]
+    FrozenEnvironment(String),
+
+    #[error("Cannot destructure {1} values into {0} names")]
+    DestructuringMismatch(usize, usize),
+
+    #[error("Maximum recursion depth of {0} calls exceeded")]
+    RecursionLimitExceeded(usize),
+
+    #[error("Evaluation timed out")]
+    Timeout,
+
+    #[error("Failed to write the output: {0}")]
+    OutputFailed(String),
+
+    #[error("Native function panicked: {0}")]
+    NativePanic(String),
+
     #[error("Return statement used outside an expression")]
     ReturnOutsideExpression,
 
+    #[error("Break statement used outside a loop")]
+    BreakOutsideLoop,
+
+    #[error("Continue statement used outside a loop")]
+    ContinueOutsideLoop,
+
+    #[error("Cannot use `()` in {0}: did a function return no value?")]
+    MissingValue(String),
+
     #[error("Unsupported operator: {0}")]
     UnsupportedOperator(TokenKind),
 
@@ -135,15 +677,33 @@ pub enum EvalError {
     #[error("Unsupported argument type for built-in function: {0}")]
     UnsupportedArgumentType(String),
 
-    #[error("Only arrays can be accessed through the index operator")]
+    #[error("Only arrays, maps, bytes and strings can be accessed through the index operator")]
     InvalidIndexUsage,
 
     #[error("This structure cannot be accessed with such type.")]
     InvalidIndexType,
 
+    #[error("Collections are limited to {0} elements")]
+    CollectionTooLarge(usize),
+
+    #[error("Duplicate map key: {0}")]
+    DuplicateMapKey(String),
+
+    #[error("Empty collection: {0}")]
+    EmptyCollection(String),
+
     #[error("This structure has {0} elements but the index {1} is out of bounds.")]
-    IndexOutOfBounds(usize, usize),
+    IndexOutOfBounds(usize, i32),
 
     #[error("This map doesn't have a value defined at key {0}")]
     ValueNotFound(String),
+
+    #[error("Invalid UTF-8 sequence: {0}")]
+    InvalidUtf8(#[from] FromUtf8Error),
+
+    #[error("Invalid base64: {0}")]
+    InvalidBase64(String),
+
+    #[error("Called `unwrap` on {0}")]
+    UnwrapFailed(String),
 }
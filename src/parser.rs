@@ -1,9 +1,9 @@
-use std::{collections::HashMap, rc::Rc};
+use std::rc::Rc;
 
 use crate::{
     ast::{Expression, ParserError, Program, Statement},
     lexer::Lexer,
-    token::{Token, TokenKind},
+    token::{Position, Token, TokenKind},
 };
 
 /// Represents the binding power of a token.
@@ -17,7 +17,7 @@ pub enum Precedence {
     Postfix(u8),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Parser<'a> {
     pub lexer: Lexer<'a>,
     pub cur: Rc<Token>,
@@ -33,10 +33,12 @@ impl<'a> Parser<'a> {
             cur: Rc::new(Token {
                 kind: TokenKind::Eof,
                 literal: "".to_owned(),
+                position: Position::default(),
             }),
             next: Rc::new(Token {
                 kind: TokenKind::Eof,
                 literal: "".to_owned(),
+                position: Position::default(),
             }),
         };
 
@@ -68,6 +70,36 @@ impl<'a> Parser<'a> {
         Ok(self.cur.clone())
     }
 
+    /// Like `expect_token(TokenKind::Identifier)` for names being bound,
+    /// but keywords in their place are reported as such.
+    fn expect_identifier(&mut self) -> Result<String, ParserError> {
+        let is_keyword = self.next.kind != TokenKind::Identifier
+            && TokenKind::lookup_identifier(&self.next.literal) == self.next.kind;
+
+        if is_keyword {
+            return Err(ParserError::ReservedKeyword(self.next.literal.clone()));
+        }
+
+        Ok(self.expect_token(TokenKind::Identifier)?.literal.clone())
+    }
+
+    /// Look at the token after `next` without consuming anything.
+    fn peek_second_token(&self) -> Token {
+        self.lexer.clone().next_token()
+    }
+
+    /// A `{` opens a map literal rather than a block when it's immediately closed (`{}`)
+    /// or followed by a `key: ...` pair, whose key is a string, integer or boolean literal.
+    fn is_map_literal_start(&self) -> bool {
+        match self.next.kind {
+            TokenKind::RightBrace => true,
+            TokenKind::String | TokenKind::Integer | TokenKind::True | TokenKind::False => {
+                self.peek_second_token().kind == TokenKind::Colon
+            }
+            _ => false,
+        }
+    }
+
     pub fn parse_program(&mut self) -> Result<Program, ParserError> {
         let mut statements: Vec<Statement> = vec![];
 
@@ -81,12 +113,26 @@ impl<'a> Parser<'a> {
 
     pub fn parse_statement(&mut self) -> Result<Statement, ParserError> {
         match self.cur.kind {
-            TokenKind::Let => self.parse_var_statement(),
+            TokenKind::Let | TokenKind::Const => self.parse_var_statement(),
             TokenKind::Return => self.parse_return_statement(),
-            TokenKind::LeftBrace => self.parse_block_statement(),
+            TokenKind::For => self.parse_for_statement(),
+            // anonymous functions are expressions, named ones are declarations
+            TokenKind::Function if self.next.kind != TokenKind::LeftParen => {
+                self.parse_function_declaration()
+            }
+            TokenKind::Break | TokenKind::Continue => self.parse_loop_control_statement(),
+            TokenKind::LeftBrace => {
+                if self.is_map_literal_start() {
+                    self.parse_expression_statement()
+                } else {
+                    self.parse_block_statement()
+                }
+            }
             TokenKind::Identifier => {
                 if self.next.kind == TokenKind::Assign {
                     self.parse_assign_statement()
+                } else if self.next.kind.compound_operator().is_some() {
+                    self.parse_compound_assign_statement()
                 } else {
                     self.parse_expression_statement()
                 }
@@ -97,14 +143,68 @@ impl<'a> Parser<'a> {
 
     pub fn parse_var_statement(&mut self) -> Result<Statement, ParserError> {
         let kind = self.cur.kind.clone();
-        let name = self.expect_token(TokenKind::Identifier)?;
+
+        if self.next.kind == TokenKind::LeftSquare {
+            return self.parse_destructuring_statement(kind);
+        }
+
+        let name = self.expect_identifier()?;
         self.expect_token(TokenKind::Assign)?;
         let expr = self.parse_expression(0, false)?;
         self.expect_token(TokenKind::Semicolon)?;
 
         Ok(Statement::VarStatement {
             kind,
-            name: name.literal.clone(),
+            name,
+            value: expr,
+        })
+    }
+
+    /// Parses `fn name(params) { body }` as `let name = fn(params) { body };`,
+    /// so that the function can call itself through the environment like any other binding.
+    pub fn parse_function_declaration(&mut self) -> Result<Statement, ParserError> {
+        let name = self.expect_identifier()?;
+        let value = self.parse_function_expression()?;
+
+        // make semicolons optional
+        if self.next.kind == TokenKind::Semicolon {
+            self.eat_token();
+        }
+
+        Ok(Statement::VarStatement {
+            kind: TokenKind::Let,
+            name,
+            value,
+        })
+    }
+
+    pub fn parse_destructuring_statement(
+        &mut self,
+        kind: TokenKind,
+    ) -> Result<Statement, ParserError> {
+        self.expect_token(TokenKind::LeftSquare)?;
+
+        let mut names: Vec<String> = vec![];
+        while self.next.kind != TokenKind::RightSquare {
+            names.push(self.expect_identifier()?);
+
+            if self.next.kind == TokenKind::Comma {
+                self.eat_token();
+            } else if self.next.kind != TokenKind::RightSquare {
+                return Err(ParserError::SyntaxError(
+                    "Expected comma between destructured names".to_owned(),
+                ));
+            }
+        }
+
+        self.expect_token(TokenKind::RightSquare)?;
+        self.expect_token(TokenKind::Assign)?;
+        let expr = self.parse_expression(0, false)?;
+        self.expect_token(TokenKind::Semicolon)?;
+
+        Ok(Statement::DestructuringStatement {
+            kind,
+            names,
             value: expr,
         })
     }
@@ -112,12 +212,23 @@ impl<'a> Parser<'a> {
     pub fn parse_return_statement(&mut self) -> Result<Statement, ParserError> {
         if self.next.kind == TokenKind::Semicolon {
             self.eat_token();
-            Ok(Statement::ReturnStatement(None))
-        } else {
-            let expr = self.parse_expression(0, false)?;
-            self.expect_token(TokenKind::Semicolon)?;
-            Ok(Statement::ReturnStatement(Some(expr)))
+            return Ok(Statement::ReturnStatement(None));
+        }
+
+        let mut expr = self.parse_expression(0, false)?;
+
+        // multiple values are returned as an array: `return a, b;` is `return [a, b];`
+        if self.next.kind == TokenKind::Comma {
+            let mut values = vec![expr];
+            while self.next.kind == TokenKind::Comma {
+                self.eat_token();
+                values.push(self.parse_expression(0, false)?);
+            }
+            expr = Expression::ArrayLiteral(values);
         }
+
+        self.expect_token(TokenKind::Semicolon)?;
+        Ok(Statement::ReturnStatement(Some(expr)))
     }
 
     pub fn parse_assign_statement(&mut self) -> Result<Statement, ParserError> {
@@ -129,12 +240,110 @@ impl<'a> Parser<'a> {
         Ok(Statement::AssignStatement { name, value: expr })
     }
 
+    /// Index assignments are only known once the target has been parsed,
+    /// so the expression before the `=` is received already parsed.
+    pub fn parse_index_assign_statement(
+        &mut self,
+        target: Expression,
+    ) -> Result<Statement, ParserError> {
+        let Expression::IndexExpression { value, index } = target else {
+            return Err(ParserError::SyntaxError(format!(
+                "cannot assign to `{target}`"
+            )));
+        };
+
+        // the assigned element must be reachable from a variable
+        let mut root = value.as_ref();
+        while let Expression::IndexExpression { value, .. } = root {
+            root = value;
+        }
+
+        if !matches!(root, Expression::Identifier(_)) {
+            return Err(ParserError::SyntaxError(format!(
+                "cannot assign to an element of `{root}`"
+            )));
+        }
+
+        self.expect_token(TokenKind::Assign)?;
+        let expr = self.parse_expression(0, false)?;
+        self.expect_token(TokenKind::Semicolon)?;
+
+        Ok(Statement::IndexAssignStatement {
+            target: value,
+            index,
+            value: expr,
+        })
+    }
+
+    /// Compound assignments are desugared: `x += e` becomes `x = x + e`.
+    pub fn parse_compound_assign_statement(&mut self) -> Result<Statement, ParserError> {
+        let name = self.cur.literal.clone();
+        self.eat_token();
+
+        let Some(operator) = self.cur.kind.compound_operator() else {
+            return Err(ParserError::UnexpectedToken(self.cur.clone()));
+        };
+
+        let expr = self.parse_expression(0, false)?;
+        self.expect_token(TokenKind::Semicolon)?;
+
+        Ok(Statement::AssignStatement {
+            name: name.clone(),
+            value: Expression::BinaryExpression {
+                left: Box::new(Expression::Identifier(name)),
+                operator,
+                right: Box::new(expr),
+            },
+        })
+    }
+
+    pub fn parse_for_statement(&mut self) -> Result<Statement, ParserError> {
+        let var = self.expect_identifier()?;
+        self.expect_token(TokenKind::In)?;
+        let iterable = self.parse_expression(0, false)?;
+        self.expect_token(TokenKind::LeftBrace)?;
+        let body = Box::new(self.parse_block_statement()?);
+
+        // make semicolons optional
+        if self.next.kind == TokenKind::Semicolon {
+            self.eat_token();
+        }
+
+        Ok(Statement::ForStatement {
+            var,
+            iterable,
+            body,
+        })
+    }
+
+    pub fn parse_loop_control_statement(&mut self) -> Result<Statement, ParserError> {
+        let statement = match self.cur.kind {
+            TokenKind::Break => Statement::BreakStatement,
+            TokenKind::Continue => Statement::ContinueStatement,
+            _ => return Err(ParserError::UnexpectedToken(self.cur.clone())),
+        };
+
+        // make semicolons optional
+        if self.next.kind == TokenKind::Semicolon {
+            self.eat_token();
+        }
+
+        Ok(statement)
+    }
+
     pub fn parse_block_statement(&mut self) -> Result<Statement, ParserError> {
+        let start = self.cur.position;
         // consume {
         self.eat_token();
         let mut statements: Vec<Statement> = vec![];
 
         while self.cur.kind != TokenKind::RightBrace {
+            if self.cur.kind == TokenKind::Eof {
+                return Err(ParserError::SyntaxError(format!(
+                    "unterminated block, expected '}}' to close the '{{' at {start}"
+                )));
+            }
+
             let statement = self.parse_statement()?;
             statements.push(statement);
             self.eat_token();
@@ -146,6 +355,10 @@ impl<'a> Parser<'a> {
     pub fn parse_expression_statement(&mut self) -> Result<Statement, ParserError> {
         let expr = self.parse_expression(0, true)?;
 
+        if self.next.kind == TokenKind::Assign {
+            return self.parse_index_assign_statement(expr);
+        }
+
         // make semicolons optional
         if self.next.kind == TokenKind::Semicolon {
             self.eat_token();
@@ -156,8 +369,16 @@ impl<'a> Parser<'a> {
 
     fn infix_precedence(op: &TokenKind) -> Option<Precedence> {
         match op {
+            // right-associative and looser than `&&` and `||`, which are parsed on its left
+            // since their right binding power is higher: `a || b ? c : d` is `(a || b) ? c : d`
+            TokenKind::Question => Some(Precedence::Infix(1, 1)),
+
             TokenKind::AndAnd | TokenKind::OrOr => Some(Precedence::Infix(1, 2)),
 
+            // left-associative, between logical operators and comparisons:
+            // `a == b |> f && c` is `((a == b) |> f) && c`
+            TokenKind::PipeForward => Some(Precedence::Infix(2, 3)),
+
             TokenKind::Equal | TokenKind::NotEqual => Some(Precedence::Infix(3, 4)),
 
             TokenKind::LessThan
@@ -165,26 +386,40 @@ impl<'a> Parser<'a> {
             | TokenKind::LessThanEqual
             | TokenKind::GreaterThanEqual => Some(Precedence::Infix(5, 6)),
 
-            TokenKind::Plus | TokenKind::Minus => Some(Precedence::Infix(7, 8)),
+            // bitwise operators bind tighter than comparisons, so that `a & 1 == 0` needs no parentheses
+            TokenKind::Pipe => Some(Precedence::Infix(7, 8)),
+
+            TokenKind::Caret => Some(Precedence::Infix(9, 10)),
+
+            TokenKind::Ampersand => Some(Precedence::Infix(11, 12)),
+
+            TokenKind::ShiftLeft | TokenKind::ShiftRight => Some(Precedence::Infix(13, 14)),
+
+            TokenKind::Plus | TokenKind::Minus => Some(Precedence::Infix(15, 16)),
 
             TokenKind::Asterisk | TokenKind::Slash | TokenKind::Percentage => {
-                Some(Precedence::Infix(9, 10))
+                Some(Precedence::Infix(17, 18))
             }
 
+            // right-associative, and binding tighter than prefix operators: `-2 ** 2` is `-(2 ** 2)`
+            TokenKind::Power => Some(Precedence::Infix(20, 19)),
+
             _ => None,
         }
     }
 
     fn prefix_precedence(op: &TokenKind) -> Option<Precedence> {
         match op {
-            TokenKind::Bang | TokenKind::Minus => Some(Precedence::Prefix(11)),
+            TokenKind::Bang | TokenKind::Minus | TokenKind::Tilde => Some(Precedence::Prefix(19)),
             _ => None,
         }
     }
 
     fn postfix_precedence(op: &TokenKind) -> Option<Precedence> {
         match op {
-            TokenKind::LeftSquare | TokenKind::LeftParen => Some(Precedence::Postfix(12)),
+            TokenKind::LeftSquare | TokenKind::LeftParen | TokenKind::Dot => {
+                Some(Precedence::Postfix(21))
+            }
             _ => None,
         }
     }
@@ -205,6 +440,7 @@ impl<'a> Parser<'a> {
             TokenKind::Integer => Expression::IntegerLiteral(self.cur.literal.parse::<i32>()?),
             TokenKind::True => Expression::BooleanLiteral(true),
             TokenKind::False => Expression::BooleanLiteral(false),
+            TokenKind::Nil => Expression::NilLiteral,
             TokenKind::String => Expression::StringLiteral(self.cur.literal.clone()),
             TokenKind::Identifier => Expression::Identifier(self.cur.literal.clone()),
 
@@ -217,10 +453,16 @@ impl<'a> Parser<'a> {
             TokenKind::LeftBrace => self.parse_map_expression()?,
 
             // parse unary expressions based on prefix token precedences
-            TokenKind::Bang | TokenKind::Minus => self.parse_unary_expression()?,
+            TokenKind::Bang | TokenKind::Minus | TokenKind::Tilde => {
+                self.parse_unary_expression()?
+            }
 
             TokenKind::If => self.parse_if_expression()?,
 
+            TokenKind::Do => self.parse_do_expression()?,
+
+            TokenKind::Match => self.parse_match_expression()?,
+
             TokenKind::Function => self.parse_function_expression()?,
 
             _ => {
@@ -249,12 +491,36 @@ impl<'a> Parser<'a> {
                             ));
                         }
 
-                        let index = Box::new(self.parse_expression(0, false)?);
-                        self.expect_token(TokenKind::RightSquare)?;
-
-                        Expression::IndexExpression {
-                            value: Box::new(expr),
-                            index,
+                        let index = if self.next.kind == TokenKind::Colon {
+                            None
+                        } else {
+                            Some(Box::new(self.parse_expression(0, false)?))
+                        };
+
+                        // a colon turns the index into the bounds of a slice
+                        if self.next.kind == TokenKind::Colon {
+                            self.eat_token();
+
+                            let end = if self.next.kind == TokenKind::RightSquare {
+                                None
+                            } else {
+                                Some(Box::new(self.parse_expression(0, false)?))
+                            };
+                            self.expect_token(TokenKind::RightSquare)?;
+
+                            Expression::SliceExpression {
+                                value: Box::new(expr),
+                                start: index,
+                                end,
+                            }
+                        } else {
+                            self.expect_token(TokenKind::RightSquare)?;
+
+                            // unwrapping is fine, the index is only missing before a colon
+                            Expression::IndexExpression {
+                                value: Box::new(expr),
+                                index: index.unwrap(),
+                            }
                         }
                     }
 
@@ -267,6 +533,19 @@ impl<'a> Parser<'a> {
                         }
                     }
 
+                    // `receiver.f(args)` is sugar for `f(receiver, args)`
+                    TokenKind::Dot => {
+                        let name = self.expect_token(TokenKind::Identifier)?.literal.clone();
+                        self.expect_token(TokenKind::LeftParen)?;
+                        let mut arguments = self.parse_expression_list(TokenKind::RightParen)?;
+                        arguments.insert(0, expr);
+
+                        Expression::CallExpression {
+                            path: Box::new(Expression::Identifier(name)),
+                            arguments,
+                        }
+                    }
+
                     _ => {
                         return Err(ParserError::UnexpectedToken(self.cur.clone()));
                     }
@@ -275,6 +554,20 @@ impl<'a> Parser<'a> {
                 continue;
             }
 
+            // parse `a if cond else b`, which binds looser than any operator
+            if min_prec == 0 && self.next.kind == TokenKind::If {
+                match self.parse_conditional_expression(expr)? {
+                    Ok(conditional) => {
+                        expr = conditional;
+                        continue;
+                    }
+                    Err(consequence) => {
+                        expr = consequence;
+                        break;
+                    }
+                }
+            }
+
             // parse binary expressions based on infix operators precedences
             if let Some(Precedence::Infix(left_prec, right_prec)) =
                 Self::infix_precedence(&self.next.kind)
@@ -292,6 +585,12 @@ impl<'a> Parser<'a> {
                     | TokenKind::Slash
                     | TokenKind::Asterisk
                     | TokenKind::Percentage
+                    | TokenKind::Power
+                    | TokenKind::Ampersand
+                    | TokenKind::Pipe
+                    | TokenKind::Caret
+                    | TokenKind::ShiftLeft
+                    | TokenKind::ShiftRight
                     | TokenKind::Equal
                     | TokenKind::NotEqual
                     | TokenKind::LessThan
@@ -308,6 +607,38 @@ impl<'a> Parser<'a> {
                             right: Box::new(right),
                         }
                     }
+
+                    // `x |> f(a)` is sugar for `f(x, a)`, and `x |> f` for `f(x)`
+                    TokenKind::PipeForward => {
+                        let right = self.parse_expression(right_prec, false)?;
+
+                        match right {
+                            Expression::CallExpression {
+                                path,
+                                mut arguments,
+                            } => {
+                                arguments.insert(0, expr);
+                                Expression::CallExpression { path, arguments }
+                            }
+                            function => Expression::CallExpression {
+                                path: Box::new(function),
+                                arguments: vec![expr],
+                            },
+                        }
+                    }
+
+                    TokenKind::Question => {
+                        // the consequence is delimited by the colon, like a grouped expression
+                        let consequence = self.parse_expression(0, false)?;
+                        self.expect_token(TokenKind::Colon)?;
+                        let alternative = self.parse_expression(right_prec, false)?;
+
+                        Expression::TernaryExpression {
+                            condition: Box::new(expr),
+                            consequence: Box::new(consequence),
+                            alternative: Box::new(alternative),
+                        }
+                    }
                     _ => {
                         return Err(ParserError::UnexpectedToken(self.cur.clone()));
                     }
@@ -324,39 +655,38 @@ impl<'a> Parser<'a> {
 
     pub fn parse_grouped_expression(&mut self) -> Result<Expression, ParserError> {
         self.eat_token();
-        let expr = match self.cur.kind {
-            TokenKind::RightParen => {
-                return Err(ParserError::SyntaxError(
-                    "Empty grouped expression '()' isn't allowed".to_owned(),
-                ))
-            }
-            _ => {
-                let subexpr = self.parse_expression(0, true)?;
-                self.expect_token(TokenKind::RightParen)?;
-                subexpr
-            }
-        };
+
+        // `()` is the empty tuple, i.e. the unit value
+        if self.cur.kind == TokenKind::RightParen {
+            return Ok(Expression::TupleLiteral(vec![]));
+        }
+
+        let expr = self.parse_expression(0, true)?;
+
+        // a comma turns the grouped expression into a tuple, even with one element: `(1,)`
+        if self.next.kind == TokenKind::Comma {
+            self.eat_token();
+
+            let mut elements = vec![expr];
+            elements.extend(self.parse_expression_list(TokenKind::RightParen)?);
+
+            return Ok(Expression::TupleLiteral(elements));
+        }
+
+        self.expect_token(TokenKind::RightParen)?;
 
         Ok(Expression::GroupedExpression(Box::new(expr)))
     }
 
     pub fn parse_map_expression(&mut self) -> Result<Expression, ParserError> {
-        let mut map = HashMap::new();
+        let mut map = vec![];
         let end = TokenKind::RightBrace;
 
         while self.next.kind != end {
-            let key = match self.parse_expression(0, false)? {
-                Expression::StringLiteral(lit) => lit,
-                _ => {
-                    return Err(ParserError::SyntaxError(
-                        "Only strings can be used as keys.".to_owned(),
-                    ))
-                }
-            };
-
+            let key = self.parse_expression(0, false)?;
             self.expect_token(TokenKind::Colon)?;
             let value = self.parse_expression(0, false)?;
-            map.insert(key, value);
+            map.push((key, value));
 
             if self.next.kind == TokenKind::Comma {
                 self.eat_token();
@@ -412,8 +742,18 @@ impl<'a> Parser<'a> {
 
         let alternative = if self.next.kind == TokenKind::Else {
             self.eat_token();
-            self.expect_token(TokenKind::LeftBrace)?;
-            Some(Box::new(self.parse_block_statement()?))
+
+            // `else if` is sugar for an `else` block holding just another if expression
+            if self.next.kind == TokenKind::If {
+                self.eat_token();
+                let expr = self.parse_if_expression()?;
+                Some(Box::new(Statement::BlockStatement(vec![
+                    Statement::ExpressionStatement(expr),
+                ])))
+            } else {
+                self.expect_token(TokenKind::LeftBrace)?;
+                Some(Box::new(self.parse_block_statement()?))
+            }
         } else {
             None
         };
@@ -425,20 +765,117 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// Parses the `if cond else b` part of a conditional expression.
+    /// Without an `else` after the condition, the `if` starts a new statement instead:
+    /// the parser is then restored and the consequence is given back.
+    pub fn parse_conditional_expression(
+        &mut self,
+        consequence: Expression,
+    ) -> Result<Result<Expression, Expression>, ParserError> {
+        let checkpoint = self.clone();
+        self.eat_token();
+
+        let condition = match self.parse_expression(1, false) {
+            Ok(condition) if self.next.kind == TokenKind::Else => condition,
+            _ => {
+                *self = checkpoint;
+                return Ok(Err(consequence));
+            }
+        };
+
+        self.eat_token();
+        let alternative = self.parse_expression(0, false)?;
+
+        Ok(Ok(Expression::ConditionalExpression {
+            consequence: Box::new(consequence),
+            condition: Box::new(condition),
+            alternative: Box::new(alternative),
+        }))
+    }
+
+    pub fn parse_do_expression(&mut self) -> Result<Expression, ParserError> {
+        self.expect_token(TokenKind::LeftBrace)?;
+        let body = self.parse_block_statement()?;
+
+        Ok(Expression::DoExpression(Box::new(body)))
+    }
+
+    /// Parses `match subject { pattern => value, ..., _ => default }`, where patterns are
+    /// integer, boolean or string literals and the optional `_` arm comes last.
+    pub fn parse_match_expression(&mut self) -> Result<Expression, ParserError> {
+        let subject = self.parse_expression(0, false)?;
+        self.expect_token(TokenKind::LeftBrace)?;
+
+        let mut arms = vec![];
+        let mut default = None;
+
+        // like expression lists, arms may be followed by a single trailing comma
+        while self.next.kind != TokenKind::RightBrace {
+            if default.is_some() {
+                return Err(ParserError::SyntaxError(
+                    "The `_` arm must be the last one of a match".to_owned(),
+                ));
+            }
+
+            self.eat_token();
+            let pattern = match self.cur.kind {
+                TokenKind::Identifier if self.cur.literal == "_" => None,
+                TokenKind::Integer => Some(Expression::IntegerLiteral(self.cur.literal.parse()?)),
+                TokenKind::Minus if self.next.kind == TokenKind::Integer => {
+                    self.eat_token();
+                    let literal = format!("-{}", self.cur.literal);
+                    Some(Expression::IntegerLiteral(literal.parse()?))
+                }
+                TokenKind::True => Some(Expression::BooleanLiteral(true)),
+                TokenKind::False => Some(Expression::BooleanLiteral(false)),
+                TokenKind::String => Some(Expression::StringLiteral(self.cur.literal.clone())),
+                _ => {
+                    return Err(ParserError::SyntaxError(
+                        "Match patterns must be integer, boolean or string literals".to_owned(),
+                    ))
+                }
+            };
+
+            self.expect_token(TokenKind::FatArrow)?;
+            let value = self.parse_expression(0, false)?;
+
+            match pattern {
+                Some(pattern) => arms.push((pattern, value)),
+                None => default = Some(Box::new(value)),
+            }
+
+            if self.next.kind == TokenKind::Comma {
+                self.eat_token();
+            } else if self.next.kind != TokenKind::RightBrace {
+                return Err(ParserError::SyntaxError(
+                    "Expected comma between match arms".to_owned(),
+                ));
+            }
+        }
+
+        self.expect_token(TokenKind::RightBrace)?;
+
+        Ok(Expression::MatchExpression {
+            subject: Box::new(subject),
+            arms,
+            default,
+        })
+    }
+
     pub fn parse_function_expression(&mut self) -> Result<Expression, ParserError> {
         self.expect_token(TokenKind::LeftParen)?;
 
+        // like expression lists, parameters may be followed by a single trailing comma
         let mut parameters: Vec<String> = vec![];
         while self.next.kind != TokenKind::RightParen {
-            if self.next.kind != TokenKind::Identifier && self.next.kind != TokenKind::Comma {
-                break;
-            }
-
-            self.expect_token(TokenKind::Identifier)?;
-            parameters.push(self.cur.literal.clone());
+            parameters.push(self.expect_identifier()?);
 
             if self.next.kind == TokenKind::Comma {
                 self.eat_token();
+            } else if self.next.kind != TokenKind::RightParen {
+                return Err(ParserError::SyntaxError(
+                    "Expected comma between parameters".to_owned(),
+                ));
             }
         }
 
@@ -482,6 +919,42 @@ mod tests {
         parser.parse_return_statement().unwrap();
     }
 
+    #[test]
+    fn parse_multiple_values_return_statement() {
+        let tests = vec![
+            ("return a;", "return a;"),
+            ("return a, b + 1;", "return [a, (b + 1)];"),
+            ("return [a, b], c;", "return [[a, b], c];"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let res = parser.parse_program().unwrap().to_string();
+            assert_eq!(expected, res);
+        }
+    }
+
+    #[test]
+    fn parse_destructuring_statement() {
+        let tests = vec![
+            ("let [a, b] = f();", "let [a, b] = f();"),
+            ("let [a] = [1];", "let [a] = [1];"),
+            ("let [] = [];", "let [] = [];"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let res = parser.parse_program().unwrap().to_string();
+            assert_eq!(expected, res);
+        }
+
+        let mut parser = Parser::new("let [a b] = f();");
+        assert!(matches!(
+            parser.parse_program(),
+            Err(ParserError::SyntaxError(_))
+        ));
+    }
+
     #[test]
     fn parse_expression_statement() {
         let input = r#"
@@ -502,23 +975,157 @@ mod tests {
     }
 
     #[test]
-    fn parse_block_statement() {
-        let input = r#"
-            { let a = 2; }
-
-            { 2 + 2; }
-        "#;
+    fn parse_compound_assign_statement() {
+        let tests = vec![
+            ("a += 1;", "a = (a + 1);"),
+            ("a -= b * 2;", "a = (a - (b * 2));"),
+            ("a *= 3;", "a = (a * 3);"),
+            ("a /= 4;", "a = (a / 4);"),
+            ("a %= 5;", "a = (a % 5);"),
+        ];
 
-        let mut parser = Parser::new(input);
-        parser.parse_block_statement().unwrap();
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let res = parser.parse_program().unwrap().to_string();
+            assert_eq!(expected, res);
+        }
     }
 
     #[test]
-    fn parse_program() {
-        let input = r#"
-            let a = 1;
-            let b = a + 1;
-            return a / b;
+    fn parse_index_assign_statement() {
+        let tests = vec![
+            ("a[0] = 1;", "a[0] = 1;"),
+            ("a[i + 1] = b * 2;", "a[(i + 1)] = (b * 2);"),
+            (r#"m["key"] = true;"#, r#"m["key"] = true;"#),
+            ("a[0][1] = 2;", "(a[0])[1] = 2;"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let res = parser.parse_program().unwrap().to_string();
+            assert_eq!(expected, res);
+        }
+
+        for input in ["f()[0] = 1;", "1 + 2 = 3;"] {
+            let mut parser = Parser::new(input);
+            assert!(matches!(
+                parser.parse_program(),
+                Err(ParserError::SyntaxError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn parse_slice_expression() {
+        let tests = vec![
+            ("[1, 2, 3, 4][1:3]", "([1, 2, 3, 4][1:3])"),
+            (r#""hello"[:2]"#, r#"("hello"[:2])"#),
+            ("a[2:]", "(a[2:])"),
+            ("a[:]", "(a[:])"),
+            ("a[-2:i + 1]", "(a[(-2):(i + 1)])"),
+            ("a[1:][0]", "((a[1:])[0])"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let res = parser.parse_program().unwrap().to_string();
+            assert_eq!(expected, res);
+        }
+
+        for input in ["a[1:2:3]", "a[:", "a[1:2"] {
+            let mut parser = Parser::new(input);
+            assert!(parser.parse_program().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_tuple_expression() {
+        let tests = vec![
+            ("(1, 2)", "(1, 2)"),
+            ("(1, (2, 3), [4])", "(1, (2, 3), [4])"),
+            ("(1,)", "(1,)"),
+            ("(1, 2,)", "(1, 2)"),
+            ("()", "()"),
+            // a single element without a trailing comma is a grouped expression
+            ("(1)", "1"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let res = parser.parse_program().unwrap().to_string();
+            assert_eq!(expected, res);
+        }
+
+        let mut parser = Parser::new("(1)");
+        assert!(matches!(
+            &parser.parse_program().unwrap().0[0],
+            Statement::ExpressionStatement(Expression::GroupedExpression(_))
+        ));
+    }
+
+    #[test]
+    fn parse_do_expression() {
+        let tests = vec![
+            ("do { 1 }", "do {1}"),
+            (
+                "let a = do { let b = 2; b * 2 };",
+                "let a = do {let b = 2;(b * 2)};",
+            ),
+            ("do { 1 } + 1", "(do {1} + 1)"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let res = parser.parse_program().unwrap().to_string();
+            assert_eq!(expected, res);
+        }
+
+        let mut parser = Parser::new("do 1");
+        assert!(parser.parse_program().is_err());
+    }
+
+    #[test]
+    fn parse_conditional_expression() {
+        let tests = vec![
+            ("let x = 1 if true else 2;", "let x = (1 if true else 2);"),
+            (
+                "1 + 2 if a > b else 3 * 4",
+                "((1 + 2) if (a > b) else (3 * 4))",
+            ),
+            ("a if b else c if d else e", "(a if b else (c if d else e))"),
+            ("[a if b else c]", "[(a if b else c)]"),
+            // without `else`, the `if` starts a new statement
+            ("a\nif b { c }", "aif b {c}"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let res = parser.parse_program().unwrap().to_string();
+            assert_eq!(expected, res);
+        }
+
+        let mut parser = Parser::new("let x = 1 if true else;");
+        assert!(parser.parse_program().is_err());
+    }
+
+    #[test]
+    fn parse_block_statement() {
+        let input = r#"
+            { let a = 2; }
+
+            { 2 + 2; }
+        "#;
+
+        let mut parser = Parser::new(input);
+        parser.parse_block_statement().unwrap();
+    }
+
+    #[test]
+    fn parse_program() {
+        let input = r#"
+            let a = 1;
+            let b = a + 1;
+            return a / b;
             a + b
         "#;
 
@@ -526,6 +1133,36 @@ mod tests {
         parser.parse_program().unwrap();
     }
 
+    #[test]
+    fn parse_comments() {
+        let tests = vec![
+            ("1 + /* c */ 2", "1 + 2"),
+            ("a /* c */ * b // c", "a * b"),
+            ("f(/* first */ a, // second\n b /* last */)", "f(a, b)"),
+            (
+                "let f = fn(x /* unused */, y) {\n  // body\n  x + y /* sum */\n};",
+                "let f = fn(x, y) { x + y };",
+            ),
+            ("{ /* empty */ }", "{}"),
+            (
+                "if /* c */ a { 1 } /* c */ else { 2 }",
+                "if a { 1 } else { 2 }",
+            ),
+            ("-/* c */a ** b", "-a ** b"),
+            ("// only a comment", ""),
+        ];
+
+        for (input, expected) in tests {
+            let program = Parser::new(input).parse_program().unwrap();
+            let expected = Parser::new(expected).parse_program().unwrap();
+            assert_eq!(program.0, expected.0, "{input}");
+            assert_eq!(program.to_string(), expected.to_string(), "{input}");
+        }
+
+        let mut parser = Parser::new("1 + /* never closed");
+        assert!(parser.parse_program().is_err());
+    }
+
     #[test]
     fn operator_precedence() {
         let tests = vec![
@@ -545,6 +1182,9 @@ mod tests {
             ("true", "true"),
             ("false", "false"),
             ("true && 2 == 2", "(true && (2 == 2))"),
+            ("true && false", "(true && false)"),
+            ("false || true", "(false || true)"),
+            ("a || b && c", "((a || b) && c)"),
             ("3 > 5 == false", "((3 > 5) == false)"),
             ("3 < 5 == true", "((3 < 5) == true)"),
             ("1 + (2 + 3) + 4", "((1 + (2 + 3)) + 4)"),
@@ -579,6 +1219,163 @@ mod tests {
         }
     }
 
+    #[test]
+    fn bitwise_precedence() {
+        let tests = vec![
+            ("a | b ^ c & d", "(a | (b ^ (c & d)))"),
+            ("a & b | c", "((a & b) | c)"),
+            ("a & 1 == 0", "((a & 1) == 0)"),
+            ("a | b < c", "((a | b) < c)"),
+            ("a & b + 1", "(a & (b + 1))"),
+            ("~a & b", "((~a) & b)"),
+            ("~-a", "(~(-a))"),
+            ("a ^ b ^ c", "((a ^ b) ^ c)"),
+            ("a || b | c", "(a || (b | c))"),
+            ("1 << 2 + 3", "(1 << (2 + 3))"),
+            ("a & 1 << b", "(a & (1 << b))"),
+            ("a >> 1 << 2", "((a >> 1) << 2)"),
+            ("a << 1 < b >> 1", "((a << 1) < (b >> 1))"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let res = parser.parse_program().unwrap().to_string();
+            assert_eq!(expected, res, "{input}");
+        }
+    }
+
+    #[test]
+    fn power_precedence() {
+        let tests = vec![
+            ("2 ** 10", "(2 ** 10)"),
+            ("2 ** 3 ** 2", "(2 ** (3 ** 2))"),
+            ("2 * 3 ** 2", "(2 * (3 ** 2))"),
+            ("2 ** 3 * 2", "((2 ** 3) * 2)"),
+            ("-2 ** 2", "(-(2 ** 2))"),
+            ("2 ** -1", "(2 ** (-1))"),
+            ("a[0] ** f(1)", "((a[0]) ** f(1))"),
+            ("1 + 2 ** 2 == 5", "((1 + (2 ** 2)) == 5)"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let res = parser.parse_program().unwrap().to_string();
+            assert_eq!(expected, res, "{input}");
+        }
+    }
+
+    #[test]
+    fn ternary_precedence() {
+        let tests = vec![
+            ("true ? 1 : 2", "(true ? 1 : 2)"),
+            ("a ? b : c ? d : e", "(a ? b : (c ? d : e))"),
+            ("a ? b ? c : d : e", "(a ? (b ? c : d) : e)"),
+            ("a || b ? c + 1 : d && e", "((a || b) ? (c + 1) : (d && e))"),
+            ("x == 1 ? -1 : f(x)[0]", "((x == 1) ? (-1) : (f(x)[0]))"),
+            ("f(a ? 1 : 2, 3)", "f((a ? 1 : 2), 3)"),
+            ("arr[a ? 0 : 1]", "(arr[(a ? 0 : 1)])"),
+            ("(a ? b : c) + 1", "((a ? b : c) + 1)"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let res = parser.parse_program().unwrap().to_string();
+            assert_eq!(expected, res, "{input}");
+        }
+
+        for input in ["a ? b", "a ? b c", "a ? : c"] {
+            let mut parser = Parser::new(input);
+            assert!(parser.parse_program().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn pipe_forward_precedence() {
+        let tests = vec![
+            ("5 |> double", "double(5)"),
+            (
+                "[1, 2, 3] |> map(double) |> len",
+                "len(map([1, 2, 3], double))",
+            ),
+            ("x |> f(1, 2)", "f(x, 1, 2)"),
+            ("a + 1 |> f", "f((a + 1))"),
+            ("a == b |> f && c", "(f((a == b)) && c)"),
+            ("a || b |> f", "(a || f(b))"),
+            ("x |> fn(y) { y }", "fn(y) {y}(x)"),
+            ("x |> f ? 1 : 2", "(f(x) ? 1 : 2)"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let res = parser.parse_program().unwrap().to_string();
+            assert_eq!(expected, res, "{input}");
+        }
+
+        let mut parser = Parser::new("x |>");
+        assert!(parser.parse_program().is_err());
+    }
+
+    #[test]
+    fn binary_minus_after_operand() {
+        let tests = vec![
+            ("a[0] - 1", "((a[0]) - 1)"),
+            ("a[0]-1", "((a[0]) - 1)"),
+            ("f() - 1", "(f() - 1)"),
+            ("f()-1", "(f() - 1)"),
+            ("(x) - 1", "(x - 1)"),
+            ("(x)-1", "(x - 1)"),
+            ("x - 1", "(x - 1)"),
+            ("x-1", "(x - 1)"),
+            ("a.len() - 1", "(len(a) - 1)"),
+            ("x - -1", "(x - (-1))"),
+            ("a[0] - -f()", "((a[0]) - (-f()))"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let program = parser.parse_program().unwrap();
+
+            assert!(
+                matches!(
+                    &program.0[..],
+                    [Statement::ExpressionStatement(
+                        Expression::BinaryExpression {
+                            operator: TokenKind::Minus,
+                            ..
+                        }
+                    )]
+                ),
+                "{input}"
+            );
+            assert_eq!(program.to_string(), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn index_expression_precedence() {
+        let tests = vec![
+            ("f(b[1], b[i + 1])", "f((b[1]), (b[(i + 1)]))"),
+            (r#"f(m["a"], -b[-1])"#, r#"f((m["a"]), (-(b[(-1)])))"#),
+            ("b[1] + b[i + 1] * 2", "((b[1]) + ((b[(i + 1)]) * 2))"),
+            (r#"m["a"] % b[0] - 1"#, r#"(((m["a"]) % (b[0])) - 1)"#),
+            (
+                "b[0] < b[1] == b[2] >= b[3]",
+                "(((b[0]) < (b[1])) == ((b[2]) >= (b[3])))",
+            ),
+            ("!b[0] && c[1] || d[2]", "(((!(b[0])) && (c[1])) || (d[2]))"),
+            ("b[i][j] * f(x)[0]", "(((b[i])[j]) * (f(x)[0]))"),
+            ("b[m[\"a\"] + 1]", "(b[((m[\"a\"]) + 1)])"),
+            ("b[0] if c[0] else b[1]", "((b[0]) if (c[0]) else (b[1]))"),
+            ("g(b[1:], 1 + b[:2][0])", "g((b[1:]), (1 + ((b[:2])[0])))"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let res = parser.parse_program().unwrap().to_string();
+            assert_eq!(expected, res, "{input}");
+        }
+    }
+
     #[test]
     fn parse_if_expression() {
         let input = r#"
@@ -596,6 +1393,30 @@ mod tests {
         parser.parse_program().unwrap();
     }
 
+    #[test]
+    fn parse_else_if_expression() {
+        let tests = vec![
+            (
+                "if a { 1 } else if b { 2 } else { 3 }",
+                "if a {1} else {if b {2} else {3}}",
+            ),
+            (
+                "if a { 1 } else if b { 2 } else if c { 3 }",
+                "if a {1} else {if b {2} else {if c {3}}}",
+            ),
+            ("if a { 1 } else if b { 2 }", "if a {1} else {if b {2}}"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let res = parser.parse_program().unwrap().to_string();
+            assert_eq!(expected, res, "{input}");
+        }
+
+        let mut parser = Parser::new("if a { 1 } else if { 2 }");
+        assert!(parser.parse_program().is_err());
+    }
+
     #[test]
     fn parse_function_expression() {
         let input = r#"
@@ -612,6 +1433,133 @@ mod tests {
         parser.parse_program().unwrap();
     }
 
+    #[test]
+    fn parse_function_declaration() {
+        let input = r#"
+            fn add(a, b) { a + b }
+            fn nothing() {};
+            let sum = add(1, 2);
+        "#;
+
+        let program = Parser::new(input).parse_program().unwrap();
+        let expected = Parser::new(
+            "let add = fn(a, b) { a + b }; let nothing = fn() {}; let sum = add(1, 2);",
+        )
+        .parse_program()
+        .unwrap();
+        assert_eq!(program.0, expected.0);
+
+        // anonymous functions are still expressions
+        let mut parser = Parser::new("fn(x) { x }(1)");
+        assert!(matches!(
+            parser.parse_program().unwrap().0[..],
+            [Statement::ExpressionStatement(_)]
+        ));
+
+        let mut parser = Parser::new("fn if() {}");
+        assert!(matches!(
+            parser.parse_program(),
+            Err(ParserError::ReservedKeyword(_))
+        ));
+
+        for input in ["fn 1() {}", "fn f {}", "fn f()"] {
+            let mut parser = Parser::new(input);
+            assert!(parser.parse_program().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_method_calls() {
+        let tests = vec![
+            ("[1, 2, 3].len()", "len([1, 2, 3])"),
+            (
+                "arr.map(double).filter(even)",
+                "filter(map(arr, double), even)",
+            ),
+            ("a.f(1, 2,)", "f(a, 1, 2)"),
+            ("-a.abs()", "(-abs(a))"),
+            ("a.get(0) + b[0].len()", "(get(a, 0) + len((b[0])))"),
+            ("(1 + 2).str()", "str((1 + 2))"),
+            ("f(x).g()", "g(f(x))"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let res = parser.parse_program().unwrap().to_string();
+            assert_eq!(expected, res, "{input}");
+        }
+
+        for input in ["a.len", "a.()", "a.1()", "a..f()"] {
+            let mut parser = Parser::new(input);
+            assert!(parser.parse_program().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_match_expression() {
+        let tests = vec![
+            (
+                r#"match n { 1 => "one", 2 => "two", _ => "many" }"#,
+                r#"match n {1 => "one", 2 => "two", _ => "many"}"#,
+            ),
+            (
+                "match f(x) + 1 { -1 => a + b, true => c, }",
+                "match (f(x) + 1) {-1 => (a + b), true => c}",
+            ),
+            ("match x { _ => 0 }", "match x {_ => 0}"),
+            ("match x {}", "match x {}"),
+            (
+                "let y = match x { 1 => 2 } * 3;",
+                "let y = (match x {1 => 2} * 3);",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let res = parser.parse_program().unwrap().to_string();
+            assert_eq!(expected, res, "{input}");
+        }
+
+        for input in [
+            "match x { _ => 0, 1 => 1 }",
+            "match x { y => 0 }",
+            "match x { 1 + 1 => 0 }",
+            "match x { 1 => 0 2 => 1 }",
+            "match x { 1 => }",
+            "match x { 1 2 }",
+        ] {
+            let mut parser = Parser::new(input);
+            assert!(parser.parse_program().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_trailing_commas() {
+        let tests = vec![
+            ("fn(a, b,) { a }", "fn(a, b) {a}"),
+            ("fn(a,) { a }", "fn(a) {a}"),
+            ("f(1, 2,)", "f(1, 2)"),
+            ("f(1,)", "f(1)"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let res = parser.parse_program().unwrap().to_string();
+            assert_eq!(expected, res);
+        }
+
+        for input in [
+            "fn(a, b,,) { a }",
+            "fn(,) { 1 }",
+            "fn(a b) { a }",
+            "f(1, 2,,)",
+            "f(,)",
+        ] {
+            let mut parser = Parser::new(input);
+            assert!(parser.parse_program().is_err(), "{input}");
+        }
+    }
+
     #[test]
     fn parse_array_expression() {
         let input = r#"
@@ -621,4 +1569,220 @@ mod tests {
         let mut parser = Parser::new(input);
         parser.parse_program().unwrap();
     }
+
+    #[test]
+    fn parse_negative_list_elements() {
+        let input = "[-1, -2, 3-1, 4 - -1]";
+
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program().unwrap();
+
+        let negative = |n| Expression::UnaryExpression {
+            operator: TokenKind::Minus,
+            value: Box::new(Expression::IntegerLiteral(n)),
+        };
+
+        assert_eq!(
+            program.0,
+            vec![Statement::ExpressionStatement(Expression::ArrayLiteral(
+                vec![
+                    negative(1),
+                    negative(2),
+                    Expression::BinaryExpression {
+                        left: Box::new(Expression::IntegerLiteral(3)),
+                        operator: TokenKind::Minus,
+                        right: Box::new(Expression::IntegerLiteral(1)),
+                    },
+                    Expression::BinaryExpression {
+                        left: Box::new(Expression::IntegerLiteral(4)),
+                        operator: TokenKind::Minus,
+                        right: Box::new(negative(1)),
+                    },
+                ]
+            ))]
+        );
+    }
+
+    #[test]
+    fn parse_negative_map_values() {
+        let input = r#"let m = {"a": -1, "b": 2 - 1};"#;
+
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program().unwrap();
+
+        let expected = vec![
+            (
+                Expression::StringLiteral("a".to_owned()),
+                Expression::UnaryExpression {
+                    operator: TokenKind::Minus,
+                    value: Box::new(Expression::IntegerLiteral(1)),
+                },
+            ),
+            (
+                Expression::StringLiteral("b".to_owned()),
+                Expression::BinaryExpression {
+                    left: Box::new(Expression::IntegerLiteral(2)),
+                    operator: TokenKind::Minus,
+                    right: Box::new(Expression::IntegerLiteral(1)),
+                },
+            ),
+        ];
+
+        assert_eq!(
+            program.0,
+            vec![Statement::VarStatement {
+                kind: TokenKind::Let,
+                name: "m".to_owned(),
+                value: Expression::MapLiteral(expected),
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_map_literal_statement() {
+        let input = r#"
+            {"a": 1, "b": 2,}
+            {}
+        "#;
+
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program().unwrap();
+
+        let expected = vec![
+            (
+                Expression::StringLiteral("a".to_owned()),
+                Expression::IntegerLiteral(1),
+            ),
+            (
+                Expression::StringLiteral("b".to_owned()),
+                Expression::IntegerLiteral(2),
+            ),
+        ];
+
+        assert_eq!(
+            program.0,
+            vec![
+                Statement::ExpressionStatement(Expression::MapLiteral(expected)),
+                Statement::ExpressionStatement(Expression::MapLiteral(vec![])),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_nil_literal() {
+        let input = "let a = nil; a == nil; [nil]";
+
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(
+            program.0[0],
+            Statement::VarStatement {
+                kind: TokenKind::Let,
+                name: "a".to_owned(),
+                value: Expression::NilLiteral,
+            }
+        );
+        assert_eq!(program.to_string(), "let a = nil;(a == nil)[nil]");
+    }
+
+    #[test]
+    fn parse_map_literal_keys() {
+        let tests = vec![
+            (r#"{1: "a", true: "b"}"#, r#"{1: "a", true: "b"}"#),
+            ("{false: 0}", "{false: 0}"),
+            ("let m = {-1: 2, k: 3};", "let m = {(-1): 2, k: 3};"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(input);
+            let res = parser.parse_program().unwrap().to_string();
+            assert_eq!(expected, res);
+        }
+    }
+
+    #[test]
+    fn parse_block_statement_with_braces() {
+        let input = r#"
+            { let a = 1; a }
+            { "foo" }
+        "#;
+
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(
+            program.0,
+            vec![
+                Statement::BlockStatement(vec![
+                    Statement::VarStatement {
+                        kind: TokenKind::Let,
+                        name: "a".to_owned(),
+                        value: Expression::IntegerLiteral(1),
+                    },
+                    Statement::ExpressionStatement(Expression::Identifier("a".to_owned())),
+                ]),
+                Statement::BlockStatement(vec![Statement::ExpressionStatement(
+                    Expression::StringLiteral("foo".to_owned())
+                )]),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_for_statement() {
+        let input = r#"
+            for x in [1, 2] {
+                println(x);
+            };
+        "#;
+
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(program.to_string(), "for x in [1, 2] {println(x)}");
+    }
+
+    #[test]
+    fn parse_reserved_keyword_names() {
+        let tests = vec![
+            ("let fn = 1;", "fn"),
+            ("const if = 1;", "if"),
+            ("fn(let) {}", "let"),
+            ("fn(a, return) {}", "return"),
+            ("let [a, nil] = [1, 2];", "nil"),
+            ("for in in [1] {}", "in"),
+        ];
+
+        for (input, keyword) in tests {
+            let mut parser = Parser::new(input);
+            let err = parser.parse_program().unwrap_err();
+
+            assert_eq!(
+                err.to_string(),
+                format!("'{keyword}' is a reserved keyword and cannot be used as a variable name"),
+                "{input}"
+            );
+        }
+
+        // other tokens in place of a name are still unexpected
+        let mut parser = Parser::new(r#"let "a" = 1;"#);
+        assert!(matches!(
+            parser.parse_program(),
+            Err(ParserError::UnexpectedToken(_))
+        ));
+    }
+
+    #[test]
+    fn parse_unterminated_block() {
+        let input = "let a = 1;\nif true {\n  1";
+
+        let mut parser = Parser::new(input);
+        let err = parser.parse_program().unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Syntax error: unterminated block, expected '}' to close the '{' at 2:9"
+        );
+    }
 }
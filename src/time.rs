@@ -0,0 +1,84 @@
+/// Format of time values when they're displayed, e.g. `2023-11-14 22:13:20`.
+pub const DEFAULT_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Formats a Unix timestamp (in seconds) as a UTC date and time.
+/// The format supports `%Y` (year), `%m` (month), `%d` (day), `%H` (hours), `%M` (minutes),
+/// `%S` (seconds) and `%%` (a literal `%`), any other character is kept as is.
+pub fn format_timestamp(timestamp: i64, format: &str) -> String {
+    let days = timestamp.div_euclid(SECONDS_PER_DAY);
+    let seconds_of_day = timestamp.rem_euclid(SECONDS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+
+    let mut formatted = String::new();
+    let mut chars = format.chars();
+
+    while let Some(char) = chars.next() {
+        if char != '%' {
+            formatted.push(char);
+            continue;
+        }
+
+        match chars.next() {
+            Some('Y') => formatted.push_str(&format!("{year:04}")),
+            Some('m') => formatted.push_str(&format!("{month:02}")),
+            Some('d') => formatted.push_str(&format!("{day:02}")),
+            Some('H') => formatted.push_str(&format!("{:02}", seconds_of_day / 3600)),
+            Some('M') => formatted.push_str(&format!("{:02}", seconds_of_day % 3600 / 60)),
+            Some('S') => formatted.push_str(&format!("{:02}", seconds_of_day % 60)),
+            Some('%') => formatted.push('%'),
+            Some(other) => {
+                formatted.push('%');
+                formatted.push(other);
+            }
+            None => formatted.push('%'),
+        }
+    }
+
+    formatted
+}
+
+/// Converts the number of days since the Unix epoch into a `(year, month, day)` date,
+/// following Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    // eras are periods of 400 years, which always have the same number of days
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    // years start in March, so that leap days are at the end of the year
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_timestamps() {
+        let tests = vec![
+            (0, DEFAULT_FORMAT, "1970-01-01 00:00:00"),
+            (1_700_000_000, DEFAULT_FORMAT, "2023-11-14 22:13:20"),
+            (951_782_400, "%d/%m/%Y", "29/02/2000"),
+            (-1, DEFAULT_FORMAT, "1969-12-31 23:59:59"),
+            (0, "100%% at %H:%M (%x)%", "100% at 00:00 (%x)%"),
+        ];
+
+        for (timestamp, format, expected) in tests {
+            assert_eq!(format_timestamp(timestamp, format), expected);
+        }
+    }
+}
@@ -4,8 +4,20 @@ use std::fmt;
 pub struct Token {
     pub kind: TokenKind,
     pub literal: String,
-    // TODO: add source location for more accurate debugging info
-    // source: Source,
+    pub position: Position,
+}
+
+/// Location of a token in the source, both line and column start from 1.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -24,6 +36,13 @@ pub enum TokenKind {
     Asterisk,
     Slash,
     Percentage,
+    Power,
+
+    PlusAssign,
+    MinusAssign,
+    AsteriskAssign,
+    SlashAssign,
+    PercentageAssign,
 
     Equal,
     NotEqual,
@@ -33,10 +52,20 @@ pub enum TokenKind {
     GreaterThanEqual,
     AndAnd,
     OrOr,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
+    ShiftLeft,
+    ShiftRight,
+    PipeForward,
 
     Comma,
     Semicolon,
     Colon,
+    Question,
+    Dot,
+    FatArrow,
 
     LeftParen,
     RightParen,
@@ -47,24 +76,52 @@ pub enum TokenKind {
 
     Function,
     Let,
+    Const,
     True,
     False,
+    Nil,
     If,
     Else,
     Return,
+    For,
+    In,
+    Break,
+    Continue,
+    Do,
+    Match,
 }
 
 impl TokenKind {
+    /// Matches compound assignment operators (e.g. `+=`) with their binary operator (e.g. `+`).
+    pub fn compound_operator(&self) -> Option<TokenKind> {
+        match self {
+            TokenKind::PlusAssign => Some(TokenKind::Plus),
+            TokenKind::MinusAssign => Some(TokenKind::Minus),
+            TokenKind::AsteriskAssign => Some(TokenKind::Asterisk),
+            TokenKind::SlashAssign => Some(TokenKind::Slash),
+            TokenKind::PercentageAssign => Some(TokenKind::Percentage),
+            _ => None,
+        }
+    }
+
     /// Matches keywords.
     pub fn lookup_identifier(identifier: &str) -> TokenKind {
         match identifier {
             "fn" => TokenKind::Function,
             "let" => TokenKind::Let,
+            "const" => TokenKind::Const,
             "true" => TokenKind::True,
             "false" => TokenKind::False,
+            "nil" => TokenKind::Nil,
             "if" => TokenKind::If,
             "else" => TokenKind::Else,
             "return" => TokenKind::Return,
+            "for" => TokenKind::For,
+            "in" => TokenKind::In,
+            "break" => TokenKind::Break,
+            "continue" => TokenKind::Continue,
+            "do" => TokenKind::Do,
+            "match" => TokenKind::Match,
             _ => TokenKind::Identifier,
         }
     }
@@ -87,6 +144,13 @@ impl fmt::Display for TokenKind {
             TokenKind::Asterisk => write!(f, "*"),
             TokenKind::Slash => write!(f, "/"),
             TokenKind::Percentage => write!(f, "%"),
+            TokenKind::Power => write!(f, "**"),
+
+            TokenKind::PlusAssign => write!(f, "+="),
+            TokenKind::MinusAssign => write!(f, "-="),
+            TokenKind::AsteriskAssign => write!(f, "*="),
+            TokenKind::SlashAssign => write!(f, "/="),
+            TokenKind::PercentageAssign => write!(f, "%="),
 
             TokenKind::Equal => write!(f, "=="),
             TokenKind::NotEqual => write!(f, "!="),
@@ -96,10 +160,20 @@ impl fmt::Display for TokenKind {
             TokenKind::GreaterThanEqual => write!(f, ">="),
             TokenKind::AndAnd => write!(f, "&&"),
             TokenKind::OrOr => write!(f, "||"),
+            TokenKind::Ampersand => write!(f, "&"),
+            TokenKind::Pipe => write!(f, "|"),
+            TokenKind::Caret => write!(f, "^"),
+            TokenKind::Tilde => write!(f, "~"),
+            TokenKind::ShiftLeft => write!(f, "<<"),
+            TokenKind::ShiftRight => write!(f, ">>"),
+            TokenKind::PipeForward => write!(f, "|>"),
 
             TokenKind::Comma => write!(f, ","),
             TokenKind::Semicolon => write!(f, ";"),
             TokenKind::Colon => write!(f, ":"),
+            TokenKind::Question => write!(f, "?"),
+            TokenKind::Dot => write!(f, "."),
+            TokenKind::FatArrow => write!(f, "=>"),
             TokenKind::LeftParen => write!(f, "("),
             TokenKind::RightParen => write!(f, ")"),
             TokenKind::LeftBrace => write!(f, "{{"),
@@ -109,17 +183,19 @@ impl fmt::Display for TokenKind {
 
             TokenKind::Function => write!(f, "fn"),
             TokenKind::Let => write!(f, "let"),
+            TokenKind::Const => write!(f, "const"),
             TokenKind::True => write!(f, "true"),
             TokenKind::False => write!(f, "false"),
+            TokenKind::Nil => write!(f, "nil"),
             TokenKind::If => write!(f, "if"),
             TokenKind::Else => write!(f, "else"),
             TokenKind::Return => write!(f, "return"),
+            TokenKind::For => write!(f, "for"),
+            TokenKind::In => write!(f, "in"),
+            TokenKind::Break => write!(f, "break"),
+            TokenKind::Continue => write!(f, "continue"),
+            TokenKind::Do => write!(f, "do"),
+            TokenKind::Match => write!(f, "match"),
         }
     }
 }
-
-// #[derive(Debug, PartialEq, Eq)]
-// pub struct Source {
-//     filename: String,
-//     pos: (u32, u32),
-// }